@@ -1,3 +1,4 @@
+use crate::ocr;
 use regex::Regex;
 use std::collections::HashSet;
 
@@ -46,6 +47,18 @@ impl Display {
     fn run(self, actions: &[Action]) -> Self {
         actions.iter().fold(self, |a, b| a.step(b))
     }
+
+    fn picture(&self) -> String {
+        (0..self.size.1).map(|y| {
+            (0..self.size.0).map(|x|
+                if self.lit.contains(&(x, y)) {'#'} else {'.'}
+            ).collect::<String>() + "\n"
+        }).collect()
+    }
+
+    fn read(&self) -> Option<String> {
+        ocr::read_points(self.lit.iter().map(|&(x, y)| (x as i64, y as i64)))
+    }
 }
 
 fn parse(text: &str) -> Vec<Action> {
@@ -70,12 +83,9 @@ fn parse(text: &str) -> Vec<Action> {
 pub fn run(content: &str) {
     let actions = parse(content);
     let res = Display::new(50, 6).run(&actions);
-    println!("{}", res.lit.len());
-    for y in 0..res.size.1 {
-        let line = (0..res.size.0).map(|x|
-            if res.lit.contains(&(x, y)) {'#'} else {'.'}
-        ).collect::<String>();
-        println!("{line}");
+    match res.read() {
+        Some(text) => println!("{} {}", res.lit.len(), text),
+        None => print!("{}\n{}", res.lit.len(), res.picture()),
     }
 }
 
@@ -90,5 +100,24 @@ mod tests {
             rotate column x=1 by 1";
         let result = super::Display::new(7, 3).run(&super::parse(TEST));
         assert_eq!(result.lit.len(), 6);
+        assert_eq!(result.picture(), ".#..#.#\n#.#....\n.#.....\n");
+    }
+
+    #[test]
+    fn letters() {
+        const SCREEN: &str = "\
+            ####.####.#..#.####..###\n\
+            ...#.#....#..#.#....#...\n\
+            ..#..###..####.###..#...\n\
+            .#...#....#..#.#.....##.\n\
+            #....#....#..#.#.......#\n\
+            ####.#....#..#.#....###.";
+        let lit = SCREEN.lines().enumerate().flat_map(|(y, line)| {
+            line.chars().enumerate().filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| (x as u32, y as u32))
+        }).collect();
+        let display = super::Display { lit, size: (24, 6) };
+        assert_eq!(display.read(), Some(String::from("ZFHFS")));
+        assert_eq!(display.picture(), SCREEN.to_string() + "\n");
     }
 }
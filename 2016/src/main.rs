@@ -26,6 +26,8 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+#[path = "../../common/ocr.rs"]
+mod ocr;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -1,3 +1,4 @@
+use crate::ocr;
 use regex::Regex;
 use std::cmp;
 use std::collections::HashSet;
@@ -57,6 +58,11 @@ impl Sky {
     min_time
   }
 
+  fn read(&self) -> Option<String> {
+    let time = self.find_time();
+    ocr::read_points(self.stars.iter().map(|t| t.at(time)))
+  }
+
   fn get_output(&self) -> String {
     let time = self.find_time();
     let set = self.stars.iter().map(|t| t.at(time)).collect::<HashSet<_>>();
@@ -69,5 +75,38 @@ impl Sky {
 
 pub fn run(content: &str) {
   let sky = Sky::parse(content);
-  println!("{}{}", sky.get_output(), sky.find_time());
+  match sky.read() {
+    Some(text) => println!("{} {}", text, sky.find_time()),
+    None => println!("{}{}", sky.get_output(), sky.find_time()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  const MESSAGE: &str = "\
+#....#..######
+#....#.......#
+.#..#........#
+.#..#.......#.
+..##.......#..
+..##......#...
+.#..#....#....
+.#..#...#.....
+#....#..#.....
+#....#..######";
+
+  #[test]
+  fn message() {
+    let stars = MESSAGE.lines().enumerate().flat_map(|(y, line)| {
+      line.chars().enumerate().filter(|&(_, c)| c == '#').map(move |(x, _)| {
+        let (x, y) = (x as i64, y as i64);
+        let velocity = (if x % 2 == 0 {1} else {-1}, y % 3 - 1);
+        super::Star { position: (x - 3 * velocity.0, y - 3 * velocity.1), velocity }
+      })
+    }).collect::<Vec<_>>();
+    let sky = super::Sky { stars };
+    assert_eq!(sky.find_time(), 3);
+    assert_eq!(sky.read(), Some(String::from("XZ")));
+    assert_eq!(sky.get_output().lines().next(), Some("#....#..######"));
+  }
 }
@@ -1,3 +1,4 @@
+use crate::ocr;
 
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
@@ -28,7 +29,11 @@ fn solve_2(data: &[u8], width: usize, height: usize) -> String {
 
 pub fn run(content: &str) {
     let bytes = content.trim_end().as_bytes();
-    println!("{}\n{}", solve_1(bytes, WIDTH, HEIGHT), solve_2(bytes, WIDTH, HEIGHT))
+    let image = solve_2(bytes, WIDTH, HEIGHT);
+    match ocr::read_bitmap(&image, 'x') {
+        Some(text) => println!("{} {}", solve_1(bytes, WIDTH, HEIGHT), text),
+        None => println!("{}\n{}", solve_1(bytes, WIDTH, HEIGHT), image),
+    }
 }
 
 #[cfg(test)]
@@ -37,4 +42,18 @@ mod tests {
     fn image() {
         assert_eq!(super::solve_2("0222112222120000".as_bytes(), 2, 2), " x\nx \n");
     }
+
+    #[test]
+    fn letters() {
+        let pixels = "\
+            1000011100\
+            1000010010\
+            1000010010\
+            1000011100\
+            1000010100\
+            1111010010";
+        let data = "2".repeat(pixels.len()) + pixels;
+        let image = super::solve_2(data.as_bytes(), 10, 6);
+        assert_eq!(super::ocr::read_bitmap(&image, 'x'), Some(String::from("LR")));
+    }
 }
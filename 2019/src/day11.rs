@@ -1,4 +1,5 @@
 use crate::intcode::IntCode;
+use crate::ocr;
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
@@ -70,11 +71,18 @@ fn output(painted: &HashSet<Position>) -> String {
     }).fold(String::new(), |a, b| a + &b + "\n")
 }
 
+fn read(painted: &HashSet<Position>) -> Option<String> {
+    ocr::read_points(painted.iter().map(|&(x, y)| (x as i64, y as i64)))
+}
+
 pub fn run(content: &str) {
     let steps = HashSet::<Position>::from_iter(
         process(content, false).0.into_iter()).len();
-    let image = output(&process(content, true).1);
-    println!("{}\n{}", steps, image)
+    let painted = process(content, true).1;
+    match read(&painted) {
+        Some(text) => println!("{} {}", steps, text),
+        None => println!("{}\n{}", steps, output(&painted)),
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +97,21 @@ mod tests {
         assert_eq!(result.0, vec![(0,0), (-1,0), (-1,1), (0,1), (0,0), (1,0), (1,-1)]);
         assert_eq!(result.1.len(), 4);
     }
+
+    #[test]
+    fn letters() {
+        let image = "\
+            .##..###...##.\n\
+            #..#.#..#.#..#\n\
+            #..#.###..#...\n\
+            ####.#..#.#...\n\
+            #..#.#..#.#..#\n\
+            #..#.###...##.";
+        let painted = image.lines().enumerate().flat_map(|(y, line)| {
+            line.chars().enumerate().filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| (x as i32 - 7, y as i32 + 3))
+        }).collect();
+        assert_eq!(super::read(&painted), Some(String::from("ABC")));
+        assert_eq!(super::output(&painted).lines().next(), Some(" xx  xxx   xx "));
+    }
 }
@@ -27,6 +27,8 @@ mod day23;
 mod day24;
 mod day25;
 mod intcode;
+#[path = "../../common/ocr.rs"]
+mod ocr;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
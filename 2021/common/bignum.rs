@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+const BASE: u64 = 1_000_000_000;
+
+// Unsigned integer of arbitrary size, stored as base 10^9 digits (lowest first).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BigUint(Vec<u64>);
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint(vec![])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn trim(mut self) -> BigUint {
+        while self.0.last() == Some(&0) { self.0.pop(); }
+        self
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(mut value: u64) -> BigUint {
+        let mut digits = vec![];
+        while value > 0 {
+            digits.push(value % BASE);
+            value /= BASE;
+        }
+        BigUint(digits)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.0.len().cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.0.len() < other.0.len() { self.0.resize(other.0.len(), 0); }
+        let mut carry = 0;
+        for i in 0..self.0.len() {
+            let sum = self.0[i] + other.0.get(i).unwrap_or(&0) + carry;
+            self.0[i] = sum % BASE;
+            carry = sum / BASE;
+        }
+        if carry > 0 { self.0.push(carry); }
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let mut result = self.clone();
+        result += other;
+        result
+    }
+}
+
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(self >= other, "subtraction underflow");
+        let mut borrow = 0;
+        let digits = self.0.iter().enumerate().map(|(i, &d)| {
+            let s = other.0.get(i).unwrap_or(&0) + borrow;
+            borrow = (d < s) as u64;
+            d + borrow * BASE - s
+        }).collect();
+        BigUint(digits).trim()
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() { return BigUint::zero(); }
+        // Each product is below 10^18, so u128 sums cannot overflow in practice.
+        let mut acc = vec![0_u128; self.0.len() + other.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            for (j, &b) in other.0.iter().enumerate() {
+                acc[i + j] += a as u128 * b as u128;
+            }
+        }
+        let mut carry = 0;
+        let digits = acc.into_iter().map(|d| {
+            let sum = d + carry;
+            carry = sum / BASE as u128;
+            (sum % BASE as u128) as u64
+        }).collect();
+        assert_eq!(carry, 0);
+        BigUint(digits).trim()
+    }
+}
+
+impl Mul<u64> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: u64) -> BigUint {
+        self * &BigUint::from(other)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.split_last() {
+            None => write!(f, "0"),
+            Some((top, rest)) => {
+                write!(f, "{}", top)?;
+                rest.iter().rev().try_for_each(|d| write!(f, "{:09}", d))
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::bignum::BigUint;
+
+pub trait Scalar: Clone + From<u64> {
+    fn is_zero(&self) -> bool;
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+}
+
+impl Scalar for BigUint {
+    fn is_zero(&self) -> bool { BigUint::is_zero(self) }
+    fn add(&self, other: &BigUint) -> BigUint { self + other }
+    fn mul(&self, other: &BigUint) -> BigUint { self * other }
+}
+
+// Integer modulo M (M must be below 2^63).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Modular<const M: u64>(pub u64);
+
+impl<const M: u64> From<u64> for Modular<M> {
+    fn from(value: u64) -> Modular<M> { Modular(value % M) }
+}
+
+impl<const M: u64> Scalar for Modular<M> {
+    fn is_zero(&self) -> bool { self.0 == 0 }
+    fn add(&self, other: &Modular<M>) -> Modular<M> { Modular((self.0 + other.0) % M) }
+    fn mul(&self, other: &Modular<M>) -> Modular<M> {
+        Modular((self.0 as u128 * other.0 as u128 % M as u128) as u64)
+    }
+}
+
+// Square matrix, stored row by row.
+#[derive(Clone, Debug)]
+pub struct Matrix<T> {
+    size: usize,
+    data: Vec<T>,
+}
+
+impl<T: Scalar> Matrix<T> {
+    pub fn zero(size: usize) -> Matrix<T> {
+        Matrix { size, data: vec![T::from(0); size * size] }
+    }
+
+    pub fn identity(size: usize) -> Matrix<T> {
+        let mut m = Matrix::zero(size);
+        for i in 0..size { m.set(i, i, T::from(1)); }
+        m
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        &self.data[row * self.size + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        self.data[row * self.size + col] = value;
+    }
+
+    pub fn increment(&mut self, row: usize, col: usize) {
+        let value = self.get(row, col).add(&T::from(1));
+        self.set(row, col, value);
+    }
+
+    pub fn mul(&self, other: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(self.size, other.size);
+        let n = self.size;
+        let mut result: Matrix<T> = Matrix::zero(n);
+        for i in 0..n {
+            for k in 0..n {
+                let a = self.get(i, k);
+                if a.is_zero() { continue; }
+                for j in 0..n {
+                    let b = other.get(k, j);
+                    if b.is_zero() { continue; }
+                    let value = result.get(i, j).add(&a.mul(b));
+                    result.set(i, j, value);
+                }
+            }
+        }
+        result
+    }
+
+    // Binary exponentiation, O(n^3 log exp).
+    pub fn pow(&self, mut exp: u64) -> Matrix<T> {
+        let mut result = Matrix::identity(self.size);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 { result = result.mul(&base); }
+            exp >>= 1;
+            if exp > 0 { base = base.mul(&base); }
+        }
+        result
+    }
+
+    pub fn apply(&self, vector: &[T]) -> Vec<T> {
+        assert_eq!(self.size, vector.len());
+        (0..self.size).map(|i| {
+            vector.iter().enumerate().fold(T::from(0), |acc, (j, v)| {
+                acc.add(&self.get(i, j).mul(v))
+            })
+        }).collect()
+    }
+}
+
+// State after `steps` applications of a linear transition (next = m * state).
+pub fn advance<T: Scalar>(m: &Matrix<T>, state: &[T], steps: u64) -> Vec<T> {
+    m.pow(steps).apply(state)
+}
@@ -1,20 +1,70 @@
+use std::env;
 use std::fs;
 
-fn calculate(hist: &Vec<i64>, days: i32) -> i64 {
-    let mut state = hist.clone();
-    for _ in 0..days {
-        let first = state[0];
-        for i in 1..9 { state[i - 1] = state[i] };
-        state[6] += first;
-        state[8] = first;
-    }
-    state.iter().sum()
+#[path = "../common/bignum.rs"]
+mod bignum;
+#[path = "../common/linalg.rs"]
+mod linalg;
+
+use bignum::BigUint;
+use linalg::{Matrix, Modular, Scalar};
+
+// Timer counts shift down each day; fish at zero reset to 6 and spawn at 8.
+fn transition<T: Scalar>() -> Matrix<T> {
+    let mut m = Matrix::zero(9);
+    for i in 1..9 { m.increment(i - 1, i); }
+    m.increment(6, 0);
+    m.increment(8, 0);
+    m
+}
+
+fn calculate<T: Scalar>(hist: &[u64], days: u64) -> T {
+    let state: Vec<T> = hist.iter().map(|&n| T::from(n)).collect();
+    linalg::advance(&transition(), &state, days).iter().fold(T::from(0), |acc, n| acc.add(n))
 }
 
 fn main() {
-    let input: Vec<i32> = fs::read_to_string("input.txt").expect("Error reading input")
-        .trim().split(',').map(|x| x.parse::<i32>().unwrap()).collect();
-    let mut hist: Vec<i64> = vec![0; 9];
-    for day in input { hist[day as usize] += 1 };
-    println!("{} {}", calculate(&hist, 80), calculate(&hist, 256))
+    let input: Vec<usize> = fs::read_to_string("input.txt").expect("Error reading input")
+        .trim().split(',').map(|x| x.parse::<usize>().unwrap()).collect();
+    let mut hist: Vec<u64> = vec![0; 9];
+    for day in input { hist[day] += 1 };
+    println!("{} {}", calculate::<BigUint>(&hist, 80), calculate::<BigUint>(&hist, 256));
+    if let Some(days) = env::args().nth(1) {
+        let count: Modular<1_000_000_007> = calculate(&hist, days.parse().unwrap());
+        println!("{} days: {} (mod 1000000007)", days, count.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculate;
+    use super::bignum::BigUint;
+    use super::linalg::Modular;
+
+    const MOD: u64 = 1_000_000_007;
+
+    fn histogram(text: &str) -> Vec<u64> {
+        let mut hist = vec![0; 9];
+        for day in text.split(',') { hist[day.parse::<usize>().unwrap()] += 1; }
+        hist
+    }
+
+    #[test]
+    fn example() {
+        let hist = histogram("3,4,3,1,2");
+        assert_eq!(calculate::<BigUint>(&hist, 18).to_string(), "26");
+        assert_eq!(calculate::<BigUint>(&hist, 80).to_string(), "5934");
+        assert_eq!(calculate::<BigUint>(&hist, 256).to_string(), "26984457539");
+    }
+
+    #[test]
+    fn modular() {
+        let mut hist = histogram("3,4,3,1,2");
+        for days in 0..1000 {
+            let count: Modular<MOD> = calculate(&histogram("3,4,3,1,2"), days);
+            assert_eq!(count.0, hist.iter().sum::<u64>() % MOD);
+            hist.rotate_left(1);
+            hist[6] = (hist[6] + hist[8]) % MOD;
+        }
+    }
 }
@@ -1,6 +1,9 @@
 use std::collections::HashSet;
 use std::fs;
 
+#[path = "../../common/ocr.rs"]
+mod ocr;
+
 #[derive(Debug)]
 enum Axis {X, Y}
 
@@ -57,6 +60,10 @@ fn main() {
     }
     
     let result = fold_all(points, folds);
+    if let Some(text) = ocr::read_points(result.iter().map(|&(x, y)| (x as i64, y as i64))) {
+        println!("{}", text);
+        return;
+    }
     let x_max = result.iter().map(|p| p.0).max().unwrap();
     let y_max = result.iter().map(|p| p.1).max().unwrap();
     for y in 0..=y_max {
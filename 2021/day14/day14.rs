@@ -1,83 +1,139 @@
-use std::collections::HashMap;
+use std::env;
 use std::fs;
 
-fn process(template: &str, rules: &Vec<(String, char)>, steps: usize) -> String {
-    let mut result = String::from(template);
-    for _ in 0..steps {
-        let clist: Vec<char> = result.chars().collect();
-        let mut chunks: Vec<String> = result.chars().map(String::from).collect();
-        for (sub, ins) in rules {
-            let m: Vec<char> = sub.chars().collect();
-            for i in 1..result.len() {
-                if clist[i - 1] == m[0] && clist[i] == m[1] {
-                    chunks[i - 1].push(*ins);
-                }
-            }
-        }
-        result = chunks.into_iter().collect();
-    }
-    result
-}
+#[path = "../common/bignum.rs"]
+mod bignum;
+#[path = "../common/linalg.rs"]
+mod linalg;
 
-fn score(polymer: String) -> usize {
-    let mut count: HashMap<char, usize> = HashMap::new();
-    for ch in polymer.chars() {
-        *count.entry(ch).or_insert(0) += 1;
-    }
-    let count_min = count.values().min().unwrap();
-    let count_max = count.values().max().unwrap();
-    return count_max - count_min;
+use bignum::BigUint;
+use linalg::{Matrix, Modular, Scalar};
+
+struct Polymer {
+    letters: Vec<char>,
+    template: Vec<usize>,
+    transition: Vec<Vec<usize>>,
 }
 
-fn process_score(template: &str, rules: &Vec<(String, char)>, steps: usize) -> usize {
-    let concat = |a: char, b: char| -> String { String::from(a) + &String::from(b) };
-    let mut count: HashMap<String, isize> = HashMap::new();
+impl Polymer {
+    fn parse(text: &str) -> Polymer {
+        let (template, rules) = text.split_once("\n\n").unwrap();
+        let mut letters: Vec<char> = text.chars().filter(|c| c.is_ascii_uppercase()).collect();
+        letters.sort_unstable();
+        letters.dedup();
+        let index = |c: char| letters.binary_search(&c).unwrap();
 
-    let first = template.chars().next().unwrap();
-    let mut last: char = first;
-    for ch in template.chars().skip(1) {
-        *count.entry(concat(last, ch)).or_insert(0) += 1;
-        last = ch;
-    }
-    for _ in 0..steps {
-        let mut mods: Vec<(String, isize)> = Vec::new();
-        for (sub, ins) in rules {
-            if let Some(c) = count.get(sub) {
-                let m: Vec<char> = sub.chars().collect();
-                mods.push((concat(m[0], *ins), *c));
-                mods.push((concat(*ins, m[1]), *c));
-                mods.push((sub.to_string(), -*c));
-            }
+        // Each pair either splits into two pairs or stays as it is.
+        let n = letters.len();
+        let mut transition: Vec<Vec<usize>> = (0..n * n).map(|p| vec![p]).collect();
+        for line in rules.lines() {
+            let (pair, insert) = line.split_once(" -> ").unwrap();
+            let p: Vec<usize> = pair.chars().map(index).collect();
+            let c = index(insert.chars().next().unwrap());
+            transition[p[0] * n + p[1]] = vec![p[0] * n + c, c * n + p[1]];
         }
-        for (sub, val) in mods {
-            *count.entry(sub).or_insert(0) += val;
+        let template = template.trim().chars().map(index).collect();
+        Polymer { letters, template, transition }
+    }
+
+    fn matrix<T: Scalar>(&self) -> Matrix<T> {
+        let mut m = Matrix::zero(self.transition.len());
+        for (from, targets) in self.transition.iter().enumerate() {
+            for &to in targets { m.increment(to, from); }
         }
+        m
     }
 
-    let mut result: HashMap<char, usize> = HashMap::new();
-    for (sub, val) in count {
-        for ch in sub.chars() {
-            *result.entry(ch).or_insert(0) += val as usize;
+    // Number of occurrences of each letter after the given number of steps.
+    fn count<T: Scalar>(&self, steps: u64) -> Vec<T> {
+        let n = self.letters.len();
+        let mut pairs = vec![0_u64; n * n];
+        for w in self.template.windows(2) { pairs[w[0] * n + w[1]] += 1; }
+        let state: Vec<T> = pairs.into_iter().map(T::from).collect();
+        let pairs = linalg::advance(&self.matrix(), &state, steps);
+
+        // Every letter but the last one starts exactly one pair.
+        let mut result: Vec<T> = (0..n).map(|_| T::from(0)).collect();
+        for (p, count) in pairs.iter().enumerate() {
+            result[p / n] = result[p / n].add(count);
         }
+        let last = *self.template.last().unwrap();
+        result[last] = result[last].add(&T::from(1));
+        result
+    }
+
+    fn score(&self, steps: u64) -> BigUint {
+        let count: Vec<BigUint> = self.count(steps);
+        let present: Vec<&BigUint> = count.iter().filter(|c| !c.is_zero()).collect();
+        *present.iter().max().unwrap() - *present.iter().min().unwrap()
     }
-    *result.entry(first).or_insert(0) += 1;
-    *result.entry(last).or_insert(0) += 1;
-    let count_min = result.values().min().unwrap();
-    let count_max = result.values().max().unwrap();
-    return (count_max - count_min) / 2;
 }
 
 fn main() {
-    let input: Vec<String> = fs::read_to_string("input.txt").expect("Error reading input")
-        .split("\n\n").map(String::from).collect();
-
-    let template: String = input[0].clone();
-    let rules: Vec<(String, char)> = input[1].lines().map(|s| {
-        let parts: Vec<&str> = s.split(" -> ").collect();
-        (String::from(parts[0]), parts[1].chars().next().unwrap())
-    }).collect();
-
-    let res_1 = score(process(&template, &rules, 10));
-    let res_2 = process_score(&template, &rules, 40);
-    println!("{} {}", res_1, res_2)
+    let input = fs::read_to_string("input.txt").expect("Error reading input");
+    let polymer = Polymer::parse(&input);
+    println!("{} {}", polymer.score(10), polymer.score(40));
+    if let Some(steps) = env::args().nth(1) {
+        let count: Vec<Modular<1_000_000_007>> = polymer.count(steps.parse().unwrap());
+        for (letter, n) in polymer.letters.iter().zip(count) {
+            println!("{}: {} (mod 1000000007)", letter, n.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Polymer;
+    use super::linalg::Modular;
+
+    const EXAMPLE: &str = "\
+NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C";
+
+    const MOD: u64 = 1_000_000_007;
+
+    #[test]
+    fn example() {
+        let polymer = Polymer::parse(EXAMPLE);
+        assert_eq!(polymer.score(10).to_string(), "1588");
+        assert_eq!(polymer.score(40).to_string(), "2188189693529");
+    }
+
+    #[test]
+    fn modular() {
+        let polymer = Polymer::parse(EXAMPLE);
+        let n = polymer.letters.len();
+        let mut pairs = vec![0_u64; n * n];
+        for w in polymer.template.windows(2) { pairs[w[0] * n + w[1]] += 1; }
+        for steps in 0..200 {
+            let mut letters = vec![0_u64; n];
+            for (p, count) in pairs.iter().enumerate() { letters[p / n] += count; }
+            letters[*polymer.template.last().unwrap()] += 1;
+            let count: Vec<Modular<MOD>> = polymer.count(steps);
+            let count: Vec<u64> = count.into_iter().map(|c| c.0).collect();
+            assert_eq!(count, letters.iter().map(|c| c % MOD).collect::<Vec<_>>());
+
+            let mut next = vec![0_u64; n * n];
+            for (p, &count) in pairs.iter().enumerate() {
+                for &q in &polymer.transition[p] { next[q] = (next[q] + count) % MOD; }
+            }
+            pairs = next;
+        }
+    }
 }
@@ -1,6 +1,8 @@
-use std::collections::BinaryHeap;
 use std::fs;
 
+#[path = "../../common/crucible.rs"]
+mod crucible;
+
 #[derive(Debug)]
 struct Grid {
     width: usize,
@@ -19,32 +21,18 @@ impl Grid {
     }
 
     fn lowest_risk(&self, span: usize) -> i32 {
-        let (size, line) = (self.data.len() * span * span, self.width * span);
-        let mut heap: BinaryHeap<(i32, usize)> = BinaryHeap::new();
-        let mut visited = vec![false; size];
-
-        heap.push((0, 0));
-        while let Some((val, idx)) = heap.pop() {
-            if visited[idx] { continue; }
-            if idx == size - 1 { return -val; }
-            visited[idx] = true;
-
-            let mut adjacent: Vec<usize> = Vec::new();
-            if idx >= line { adjacent.push(idx - line); }
-            if idx < size - line { adjacent.push(idx + line); }
-            if idx % line != 0 { adjacent.push(idx - 1); }
-            if idx % line != line - 1 { adjacent.push(idx + 1); }
-
-            for next in adjacent {
-                let (i, j) = (next / line, next % line);
-                let (ty, py) = (i / self.height, i % self.height);
-                let (tx, px) = (j / self.width, j % self.width);
-                let raw = self.data[py * self.width + px] as i32;
-                let risk = ((raw + tx as i32 + ty as i32) - 1) % 9 + 1;
-                heap.push((val - risk, next));
-            }
-        }
-        panic!("unexpected")
+        let (width, height) = (self.width * span, self.height * span);
+        let risk = |(x, y): crucible::Point| {
+            let (tx, px) = (x / self.width, x % self.width);
+            let (ty, py) = (y / self.height, y % self.height);
+            let raw = self.data[py * self.width + px] as u64;
+            Some((raw + tx as u64 + ty as u64 - 1) % 9 + 1)
+        };
+        let end = (width - 1, height - 1);
+        // Risks are positive, so turning back never pays off.
+        let rules = crucible::Rules { heuristic: true, ..crucible::Rules::crucible(1, usize::MAX) };
+        let path = crucible::shortest_path(width, height, risk, (0, 0), end, &rules);
+        path.expect("unexpected").total as i32
     }
 }
 
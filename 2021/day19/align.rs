@@ -0,0 +1,384 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub type Point = (i32, i32, i32);
+pub type Matrix = [[i32; 3]; 3];
+
+const IDENTITY: Matrix = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+
+fn determinant(m: &Matrix) -> i32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [[0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            m[i][j] = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+fn rotate(m: &Matrix, p: &Point) -> Point {
+    (m[0][0] * p.0 + m[0][1] * p.1 + m[0][2] * p.2,
+     m[1][0] * p.0 + m[1][1] * p.1 + m[1][2] * p.2,
+     m[2][0] * p.0 + m[2][1] * p.1 + m[2][2] * p.2)
+}
+
+fn add(a: &Point, b: &Point) -> Point {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+fn diff(a: &Point, b: &Point) -> Point {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+fn distance(a: &Point, b: &Point) -> i32 {
+    let d = diff(a, b);
+    d.0 * d.0 + d.1 * d.1 + d.2 * d.2
+}
+
+// Signed permutation matrices with determinant 1 (no reflections).
+pub fn rotations() -> Vec<Matrix> {
+    let axes = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+    let mut result = Vec::with_capacity(24);
+    for perm in axes {
+        for signs in 0..8 {
+            let mut m = [[0; 3]; 3];
+            for (row, &col) in perm.iter().enumerate() {
+                m[row][col] = if signs >> row & 1 != 0 { -1 } else { 1 };
+            }
+            if determinant(&m) == 1 { result.push(m); }
+        }
+    }
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub rotation: Matrix,
+    pub translation: Point,
+}
+
+impl Pose {
+    pub fn identity() -> Pose {
+        Pose { rotation: IDENTITY, translation: (0, 0, 0) }
+    }
+
+    pub fn apply(&self, p: &Point) -> Point {
+        add(&rotate(&self.rotation, p), &self.translation)
+    }
+
+    // Pose of `inner` expressed in the frame this pose maps into.
+    pub fn compose(&self, inner: &Pose) -> Pose {
+        Pose {
+            rotation: multiply(&self.rotation, &inner.rotation),
+            translation: self.apply(&inner.translation),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub beacons: Vec<Point>,
+    signatures: Vec<HashSet<i32>>,
+    fingerprint: Vec<i32>,
+}
+
+impl Report {
+    pub fn new(beacons: Vec<Point>) -> Report {
+        let signatures: Vec<HashSet<i32>> = beacons.iter().map(|a| {
+            beacons.iter().filter(|b| *b != a).map(|b| distance(a, b)).collect()
+        }).collect();
+        let mut fingerprint = Vec::new();
+        for i in 0..beacons.len() {
+            for j in i+1..beacons.len() {
+                fingerprint.push(distance(&beacons[i], &beacons[j]));
+            }
+        }
+        fingerprint.sort_unstable();
+        Report { beacons, signatures, fingerprint }
+    }
+
+    pub fn parse(text: &str) -> Report {
+        Report::new(text.lines().skip(1).map(|line| {
+            let a: Vec<i32> = line.split(',').map(|s| s.parse::<i32>().unwrap()).collect();
+            (a[0], a[1], a[2])
+        }).collect())
+    }
+}
+
+// Number of pairwise distances (with multiplicity) shared by two reports.
+fn common_distances(a: &[i32], b: &[i32]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] { i += 1; }
+        else if a[i] > b[j] { j += 1; }
+        else { count += 1; i += 1; j += 1; }
+    }
+    count
+}
+
+pub struct Aligner {
+    rotations: Vec<Matrix>,
+    min_overlap: usize,
+}
+
+impl Aligner {
+    pub fn new(min_overlap: usize) -> Aligner {
+        Aligner { rotations: rotations(), min_overlap }
+    }
+
+    pub fn may_overlap(&self, a: &Report, b: &Report) -> bool {
+        let n = self.min_overlap;
+        common_distances(&a.fingerprint, &b.fingerprint) >= n * n.saturating_sub(1) / 2
+    }
+
+    // Beacon pairs whose distance signatures agree closely enough to be the same beacon.
+    fn candidates(&self, a: &Report, b: &Report) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        for (i, sa) in a.signatures.iter().enumerate() {
+            for (j, sb) in b.signatures.iter().enumerate() {
+                if sa.intersection(sb).count() + 1 >= self.min_overlap {
+                    result.push((i, j));
+                }
+            }
+        }
+        result
+    }
+
+    // Pose that maps `b` coordinates into the frame of `a`.
+    pub fn relative_pose(&self, a: &Report, b: &Report) -> Option<Pose> {
+        if !self.may_overlap(a, b) { return None; }
+        let pairs = self.candidates(a, b);
+        if pairs.len() < self.min_overlap { return None; }
+
+        let known: HashSet<Point> = a.beacons.iter().copied().collect();
+        for rotation in &self.rotations {
+            let mut votes: HashMap<Point, usize> = HashMap::new();
+            for &(i, j) in &pairs {
+                let delta = diff(&a.beacons[i], &rotate(rotation, &b.beacons[j]));
+                *votes.entry(delta).or_default() += 1;
+            }
+            for (translation, count) in votes {
+                if count < self.min_overlap { continue; }
+                let pose = Pose { rotation: *rotation, translation };
+                let matched = b.beacons.iter().filter(
+                    |p| known.contains(&pose.apply(p))).count();
+                if matched >= self.min_overlap { return Some(pose); }
+            }
+        }
+        None
+    }
+
+    // Poses of all reports in the frame of the first one, or None if some
+    // report cannot be connected to it.
+    pub fn align(&self, reports: &[Report]) -> Option<Vec<Pose>> {
+        let mut poses: Vec<Option<Pose>> = vec![None; reports.len()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        if !reports.is_empty() {
+            poses[0] = Some(Pose::identity());
+            queue.push_back(0);
+        }
+        while let Some(i) = queue.pop_front() {
+            let base = poses[i].unwrap();
+            for j in 0..reports.len() {
+                if poses[j].is_some() { continue; }
+                if let Some(pose) = self.relative_pose(&reports[i], &reports[j]) {
+                    poses[j] = Some(base.compose(&pose));
+                    queue.push_back(j);
+                }
+            }
+        }
+        poses.into_iter().collect()
+    }
+}
+
+pub fn merge(reports: &[Report], poses: &[Pose]) -> Vec<Point> {
+    let mut points: Vec<Point> = reports.iter().zip(poses).flat_map(
+        |(r, pose)| r.beacons.iter().map(move |p| pose.apply(p))).collect();
+    points.sort_unstable();
+    points.dedup();
+    points
+}
+
+pub fn export(points: &[Point]) -> String {
+    points.iter().map(|p| format!("{},{},{}\n", p.0, p.1, p.2)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rotate, rotations, Aligner, Pose, Report};
+    use std::collections::HashSet;
+
+    const EXAMPLE: &str = "\
+--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14";
+
+    #[test]
+    fn proper_rotations() {
+        let all = rotations();
+        assert_eq!(all.len(), 24);
+        let distinct: HashSet<_> = all.iter().map(|m| rotate(m, &(1, 2, 3))).collect();
+        assert_eq!(distinct.len(), 24);
+    }
+
+    #[test]
+    fn compose() {
+        let all = rotations();
+        let outer = Pose { rotation: all[5], translation: (10, -20, 30) };
+        let inner = Pose { rotation: all[17], translation: (-4, 7, 1) };
+        let both = outer.compose(&inner);
+        for p in [(0, 0, 0), (1, 2, 3), (-5, 8, -13)] {
+            assert_eq!(both.apply(&p), outer.apply(&inner.apply(&p)));
+        }
+        assert_eq!(Pose::identity().compose(&inner), inner);
+    }
+
+    #[test]
+    fn example() {
+        let reports: Vec<Report> = EXAMPLE.split("\n\n").map(Report::parse).collect();
+        let poses = Aligner::new(12).align(&reports).unwrap();
+        assert_eq!(super::merge(&reports, &poses).len(), 79);
+        let positions: Vec<_> = poses.iter().map(|p| p.translation).collect();
+        assert_eq!(positions, [(0, 0, 0), (68, -1246, -43), (1105, -1205, 1229),
+                               (-92, -2380, -20), (-20, -1133, 1061)]);
+        assert!(Aligner::new(0).may_overlap(&reports[0], &reports[1]));
+    }
+}
@@ -1,91 +1,70 @@
-use std::cmp;
-use std::collections::HashMap;
+use std::fs;
 
-const PLAYER_1: u32 = 4;
-const PLAYER_2: u32 = 10;
+mod dirac;
 
-struct Die {
-    value: u32,
-    limit: u32,
-}
+use dirac::Game;
 
-impl Iterator for Die {
-    type Item = u32;
+fn main() {
+    let start: Vec<u32> = fs::read_to_string("input.txt").expect("Error reading input")
+        .lines().map(|s| s.rsplit(' ').next().unwrap().parse().unwrap()).collect();
 
-    fn next(&mut self) -> Option<u32> {
-        self.value = self.value % self.limit + 1;
-        Some(self.value)
-    }
+    let practice = Game::new(10, 100, 3, 1000).unwrap();
+    let (scores, n) = practice.play_deterministic(&start);
+    let quantum = Game::new(10, 3, 3, 21).unwrap();
+    let wins = quantum.play_quantum(&start);
+    println!("{} {}", scores.iter().min().unwrap() * n as u32, wins.iter().max().unwrap())
 }
 
-fn play_deterministic(limit: u32) -> (u32, u32, usize) {
-    let mut die = Die { value: 0, limit: 100 };
-    let mut pos = [PLAYER_1, PLAYER_2];
-    let mut score = [0_u32; 2];
-    let mut steps: usize = 0;
+#[cfg(test)]
+mod tests {
+    use super::dirac::Game;
+    use std::collections::HashMap;
 
-    loop {
-        let roll: u32 = (&mut die).take(3).sum();
-        let index = steps % 2;
-        pos[index] = (pos[index] + roll - 1) % 10 + 1;
-        score[index] += pos[index];
-        steps += 3;
-        if score[index] >= limit { break; }
+    #[test]
+    fn example() {
+        let (scores, n) = Game::new(10, 100, 3, 1000).unwrap().play_deterministic(&[4, 8]);
+        assert_eq!(scores.iter().min().unwrap() * n as u32, 739785);
+        let wins = Game::new(10, 3, 3, 21).unwrap().play_quantum(&[4, 8]);
+        let wins: Vec<String> = wins.iter().map(|w| w.to_string()).collect();
+        assert_eq!(wins, ["444356092776315", "341960390180808"]);
     }
-    (score[0], score[1], steps)
-}
-
-#[derive(Eq, Hash, PartialEq)]
-struct State {
-    pos_1: u8,
-    score_1: u8,
-    pos_2: u8,
-    score_2: u8,
-}
-
-fn play_quantum(limit: u8) -> (u64, u64) {
-    let normal: [u64; 7] = [1, 3, 6, 7, 6, 3, 1];
-    let mut result = [0_u64; 2];
-    let mut steps: usize = 0;
-    let mut current: HashMap<State, u64> = HashMap::new();
-    current.insert(State {
-        pos_1: PLAYER_1 as u8,
-        score_1: 0,
-        pos_2: PLAYER_2 as u8,
-        score_2: 0,
-    }, 1);
 
-    while !current.is_empty() {
-        let first = steps % 2 == 0;
-        let mut next: HashMap<State, u64> = HashMap::new();
-        for (key, value) in current.iter() {
-            let pos = if first {key.pos_1} else {key.pos_2};
-            let score = if first {key.score_1} else {key.score_2};
-            for sum in 3..=9 {
-                let next_pos = (pos + sum - 1) % 10 + 1;
-                let next_score = score + next_pos;
-                let next_value = value * normal[sum as usize - 3];
-
-                if next_score >= limit {
-                    result[!first as usize] += next_value;
-                    continue;
-                }
-                let next_key = if first {
-                    State { pos_1: next_pos, score_1: next_score, ..*key }
-                } else {
-                    State { pos_2: next_pos, score_2: next_score, ..*key }
-                };
-                *next.entry(next_key).or_default() += next_value;
+    // Universes won by each player, following every roll of every turn.
+    fn brute(board: u32, faces: u32, rolls: u32, target: u32, pos: Vec<u32>, score: Vec<u32>,
+             turn: usize, memo: &mut HashMap<(Vec<u32>, Vec<u32>, usize), Vec<u64>>) -> Vec<u64> {
+        let key = (pos.clone(), score.clone(), turn);
+        if let Some(wins) = memo.get(&key) { return wins.clone(); }
+        let mut wins = vec![0; pos.len()];
+        let mut totals = vec![0_u32];
+        for _ in 0..rolls {
+            totals = totals.iter().flat_map(|t| (1..=faces).map(move |f| t + f)).collect();
+        }
+        for roll in totals {
+            let (mut pos, mut score) = (pos.clone(), score.clone());
+            pos[turn] = (pos[turn] + roll - 1) % board + 1;
+            score[turn] += pos[turn];
+            if score[turn] >= target {
+                wins[turn] += 1;
+                continue;
             }
+            let next = brute(board, faces, rolls, target, pos, score, (turn + 1) % wins.len(), memo);
+            for (w, n) in wins.iter_mut().zip(next) { *w += n; }
         }
-        steps += 1;
-        current = next;
+        memo.insert(key, wins.clone());
+        wins
     }
-    (result[0], result[1])
-}
 
-fn main() {
-    let (s1, s2, n) = play_deterministic(1000);
-    let (u1, u2) = play_quantum(21);
-    println!("{} {}", cmp::min(s1, s2) * n as u32, cmp::max(u1, u2))
+    #[test]
+    fn variant() {
+        let start = [2, 5, 7];
+        let wins = Game::new(7, 2, 2, 15).unwrap().play_quantum(&start);
+        let expected = brute(7, 2, 2, 15, start.to_vec(), vec![0; 3], 0, &mut HashMap::new());
+        let expected: Vec<String> = expected.iter().map(|w| w.to_string()).collect();
+        assert_eq!(wins.iter().map(|w| w.to_string()).collect::<Vec<_>>(), expected);
+
+        let (scores, n) = Game::new(7, 2, 2, 15).unwrap().play_deterministic(&start);
+        assert_eq!((scores, n), (vec![17, 12, 11], 20));
+        assert!(Game::new(0, 3, 3, 21).is_err());
+        assert!(Game::new(10, 0, 3, 21).is_err());
+    }
 }
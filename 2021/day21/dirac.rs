@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+#[path = "../common/bignum.rs"]
+mod bignum;
+
+use self::bignum::BigUint;
+
+// Each player rolls a `faces`-sided die `rolls` times per turn and moves
+// around a circular board of `board` spaces numbered from 1; the first to
+// reach `target` points wins.
+#[derive(Clone, Copy, Debug)]
+pub struct Game {
+    board: u32,
+    faces: u32,
+    rolls: u32,
+    target: u32,
+}
+
+impl Game {
+    pub fn new(board: u32, faces: u32, rolls: u32, target: u32) -> Result<Game, String> {
+        if board == 0 { return Err("The board needs at least one space".to_string()); }
+        if faces == 0 { return Err("The die needs at least one face".to_string()); }
+        Ok(Game { board, faces, rolls, target })
+    }
+
+    fn advance(&self, pos: u32, roll: u32) -> u32 {
+        (pos + roll - 1) % self.board + 1
+    }
+
+    pub fn play_deterministic(&self, start: &[u32]) -> (Vec<u32>, usize) {
+        let mut die = (1..=self.faces).cycle();
+        let mut pos = start.to_vec();
+        let mut score = vec![0_u32; start.len()];
+        let mut rolls: usize = 0;
+
+        for index in (0..start.len()).cycle() {
+            let roll: u32 = (&mut die).take(self.rolls as usize).sum();
+            pos[index] = self.advance(pos[index], roll);
+            score[index] += pos[index];
+            rolls += self.rolls as usize;
+            if score[index] >= self.target { break; }
+        }
+        (score, rolls)
+    }
+
+    // Number of ways to roll each total in a single turn.
+    fn distribution(&self) -> Vec<(u32, u64)> {
+        let mut ways: Vec<u64> = vec![1];
+        for _ in 0..self.rolls {
+            let mut next = vec![0_u64; ways.len() + self.faces as usize];
+            for (sum, &n) in ways.iter().enumerate() {
+                for face in 1..=self.faces as usize {
+                    next[sum + face] += n;
+                }
+            }
+            ways = next;
+        }
+        ways.into_iter().enumerate().filter(|&(_, n)| n > 0)
+            .map(|(sum, n)| (sum as u32, n)).collect()
+    }
+
+    // Universes in which a single player first reaches the target on each
+    // turn, and universes in which the player is still short of it after
+    // each turn (starting with turn zero).
+    fn profile(&self, start: u32, rolls: &[(u32, u64)]) -> (Vec<BigUint>, Vec<BigUint>) {
+        let mut current: HashMap<(u32, u32), BigUint> = HashMap::new();
+        current.insert((start, 0), BigUint::from(1));
+        let mut finished = vec![];
+        let mut playing = vec![BigUint::from(1)];
+
+        while !current.is_empty() {
+            let mut next: HashMap<(u32, u32), BigUint> = HashMap::new();
+            let mut done = BigUint::zero();
+            for (&(pos, score), count) in &current {
+                for &(roll, ways) in rolls {
+                    let next_pos = self.advance(pos, roll);
+                    let next_score = score + next_pos;
+                    let universes = count * ways;
+                    if next_score >= self.target {
+                        done += &universes;
+                    } else {
+                        *next.entry((next_pos, next_score)).or_default() += &universes;
+                    }
+                }
+            }
+            finished.push(done);
+            playing.push(next.values().fold(BigUint::zero(), |acc, n| &acc + n));
+            current = next;
+        }
+        (finished, playing)
+    }
+
+    // Number of universes in which each player wins.
+    pub fn play_quantum(&self, start: &[u32]) -> Vec<BigUint> {
+        // Players roll independently, so a win on turn `t` multiplies the
+        // player's own count by the universes where everyone else is still
+        // playing: those before it have had `t` turns, the rest `t - 1`.
+        let rolls = self.distribution();
+        let profiles: Vec<_> = start.iter().map(|&p| self.profile(p, &rolls)).collect();
+        let playing = |q: usize, turns: usize| {
+            profiles[q].1.get(turns).cloned().unwrap_or_default()
+        };
+        (0..start.len()).map(|p| {
+            let mut wins = BigUint::zero();
+            for (t, done) in profiles[p].0.iter().enumerate() {
+                let mut universes = done.clone();
+                for q in (0..start.len()).filter(|&q| q != p) {
+                    universes = &universes * &playing(q, if q < p { t + 1 } else { t });
+                }
+                wins += &universes;
+            }
+            wins
+        }).collect()
+    }
+}
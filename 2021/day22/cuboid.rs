@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Axis-aligned box of lattice cells, bounds are inclusive.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Cuboid {
+    pub min: [i64; 3],
+    pub max: [i64; 3],
+}
+
+impl Cuboid {
+    pub fn new(min: [i64; 3], max: [i64; 3]) -> Cuboid {
+        assert!((0..3).all(|i| min[i] <= max[i]), "empty cuboid");
+        Cuboid { min, max }
+    }
+
+    // Parses "x=a..b,y=c..d,z=e..f".
+    pub fn parse(text: &str) -> Cuboid {
+        let bounds: Vec<Vec<i64>> = text.split(',').map(|s| {
+            s[2..].split("..").map(|v| v.parse::<i64>().unwrap()).collect()
+        }).collect();
+        Cuboid::new([bounds[0][0], bounds[1][0], bounds[2][0]],
+                    [bounds[0][1], bounds[1][1], bounds[2][1]])
+    }
+
+    pub fn volume(&self) -> i64 {
+        (0..3).map(|i| self.max[i] - self.min[i] + 1).product()
+    }
+
+    pub fn intersect(&self, other: &Cuboid) -> Option<Cuboid> {
+        let min = [0, 1, 2].map(|i| self.min[i].max(other.min[i]));
+        let max = [0, 1, 2].map(|i| self.max[i].min(other.max[i]));
+        if (0..3).all(|i| min[i] <= max[i]) { Some(Cuboid { min, max }) } else { None }
+    }
+}
+
+pub trait CuboidSet {
+    fn add(&mut self, cuboid: &Cuboid);
+    fn subtract(&mut self, cuboid: &Cuboid);
+    fn intersect(&mut self, cuboid: &Cuboid);
+    fn volume_within(&self, region: &Cuboid) -> i64;
+    fn volume(&self) -> i64;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    Signed,
+    Compressed,
+}
+
+impl Backend {
+    pub fn create(&self) -> Box<dyn CuboidSet> {
+        match self {
+            Backend::Signed => Box::new(SignedVolumes::default()),
+            Backend::Compressed => Box::new(CompressedGrid::default()),
+        }
+    }
+}
+
+// Inclusion-exclusion: every cuboid carries a multiplicity, and the set
+// volume is the weighted sum of all volumes.
+#[derive(Default)]
+pub struct SignedVolumes {
+    parts: HashMap<Cuboid, i64>,
+}
+
+impl SignedVolumes {
+    fn cancel(&mut self, cuboid: &Cuboid) {
+        let mut update: HashMap<Cuboid, i64> = HashMap::new();
+        for (part, &sign) in &self.parts {
+            if let Some(common) = part.intersect(cuboid) {
+                *update.entry(common).or_default() -= sign;
+            }
+        }
+        for (part, sign) in update {
+            *self.parts.entry(part).or_default() += sign;
+        }
+        self.parts.retain(|_, sign| *sign != 0);
+    }
+}
+
+impl CuboidSet for SignedVolumes {
+    fn add(&mut self, cuboid: &Cuboid) {
+        self.cancel(cuboid);
+        *self.parts.entry(*cuboid).or_default() += 1;
+    }
+
+    fn subtract(&mut self, cuboid: &Cuboid) {
+        self.cancel(cuboid);
+    }
+
+    fn intersect(&mut self, cuboid: &Cuboid) {
+        let mut parts: HashMap<Cuboid, i64> = HashMap::new();
+        for (part, sign) in self.parts.drain() {
+            if let Some(common) = part.intersect(cuboid) {
+                *parts.entry(common).or_default() += sign;
+            }
+        }
+        parts.retain(|_, sign| *sign != 0);
+        self.parts = parts;
+    }
+
+    fn volume_within(&self, region: &Cuboid) -> i64 {
+        self.parts.iter().filter_map(|(part, sign)| {
+            Some(part.intersect(region)?.volume() * sign)
+        }).sum()
+    }
+
+    fn volume(&self) -> i64 {
+        self.parts.iter().map(|(part, sign)| part.volume() * sign).sum()
+    }
+}
+
+// Sets or clears bits `range` of a bitset row.
+fn fill(row: &mut [u64], range: Range<usize>, value: bool) {
+    if range.is_empty() { return; }
+    let first = range.start / 64;
+    for (w, word) in row.iter_mut().enumerate().take((range.end - 1) / 64 + 1).skip(first) {
+        let lo = range.start.max(w * 64) - w * 64;
+        let hi = range.end.min(w * 64 + 64) - w * 64;
+        let mask = if hi - lo == 64 { !0 } else { ((1 << (hi - lo)) - 1) << lo };
+        if value { *word |= mask; } else { *word &= !mask; }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Subtract,
+    Intersect,
+}
+
+// Keeps the operation log and replays it on a bitset over the grid formed
+// by all cuboid boundaries, so every compressed cell is either on or off.
+#[derive(Default)]
+pub struct CompressedGrid {
+    ops: Vec<(Op, Cuboid)>,
+}
+
+impl CompressedGrid {
+    fn evaluate(&self, region: Option<&Cuboid>) -> i64 {
+        let boxes = self.ops.iter().map(|(_, c)| c).chain(region);
+        let mut axes: [Vec<i64>; 3] = Default::default();
+        for c in boxes {
+            for (i, axis) in axes.iter_mut().enumerate() {
+                axis.push(c.min[i]);
+                axis.push(c.max[i] + 1);
+            }
+        }
+        for axis in axes.iter_mut() {
+            axis.sort_unstable();
+            axis.dedup();
+        }
+        let index = |i: usize, v: i64| axes[i].binary_search(&v).unwrap();
+        let span = |c: &Cuboid, i: usize| index(i, c.min[i])..index(i, c.max[i] + 1);
+        let size = [0, 1, 2].map(|i| axes[i].len().saturating_sub(1));
+        let words = size[2].div_ceil(64);
+        let mut bits = vec![0_u64; size[0] * size[1] * words];
+
+        let row = |x: usize, y: usize| (x * size[1] + y) * words;
+        for &(op, ref c) in &self.ops {
+            let (xs, ys, zs) = (span(c, 0), span(c, 1), span(c, 2));
+            match op {
+                Op::Add | Op::Subtract => {
+                    for x in xs.clone() {
+                        for y in ys.clone() {
+                            let r = row(x, y);
+                            fill(&mut bits[r..r + words], zs.clone(), matches!(op, Op::Add));
+                        }
+                    }
+                },
+                Op::Intersect => {
+                    for x in 0..size[0] {
+                        for y in 0..size[1] {
+                            let r = row(x, y);
+                            if xs.contains(&x) && ys.contains(&y) {
+                                fill(&mut bits[r..r + words], 0..zs.start, false);
+                                fill(&mut bits[r..r + words], zs.end..size[2], false);
+                            } else {
+                                bits[r..r + words].fill(0);
+                            }
+                        }
+                    }
+                },
+            }
+        }
+
+        let bounds = region.map(|r| [span(r, 0), span(r, 1), span(r, 2)]);
+        let width = |i: usize, k: usize| axes[i][k + 1] - axes[i][k];
+        let mut total = 0;
+        for x in 0..size[0] {
+            for y in 0..size[1] {
+                if let Some(b) = &bounds {
+                    if !b[0].contains(&x) || !b[1].contains(&y) { continue; }
+                }
+                let r = row(x, y);
+                let area = width(0, x) * width(1, y);
+                for (w, &word) in bits[r..r + words].iter().enumerate() {
+                    let mut word = word;
+                    while word != 0 {
+                        let z = w * 64 + word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        if bounds.as_ref().is_some_and(|b| !b[2].contains(&z)) { continue; }
+                        total += area * width(2, z);
+                    }
+                }
+            }
+        }
+        total
+    }
+}
+
+impl CuboidSet for CompressedGrid {
+    fn add(&mut self, cuboid: &Cuboid) {
+        self.ops.push((Op::Add, *cuboid));
+    }
+
+    fn subtract(&mut self, cuboid: &Cuboid) {
+        self.ops.push((Op::Subtract, *cuboid));
+    }
+
+    fn intersect(&mut self, cuboid: &Cuboid) {
+        self.ops.push((Op::Intersect, *cuboid));
+    }
+
+    fn volume_within(&self, region: &Cuboid) -> i64 {
+        self.evaluate(Some(region))
+    }
+
+    fn volume(&self) -> i64 {
+        self.evaluate(None)
+    }
+}
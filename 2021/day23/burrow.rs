@@ -0,0 +1,255 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cell {
+    Hall(usize),
+    Room(usize, usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Step {
+    pub kind: u8,
+    pub from: Cell,
+    pub to: Cell,
+    pub cost: usize,
+}
+
+// Hallway cells first, then rooms one after another (top to bottom).
+// Zero marks an empty cell, otherwise the amphipod letter.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct State(Vec<u8>);
+
+pub struct Burrow {
+    template: Vec<Vec<u8>>,
+    hallway: Vec<(usize, usize)>,
+    rooms: Vec<Vec<(usize, usize)>>,
+    doors: Vec<usize>,
+    offsets: Vec<usize>,
+    start: State,
+}
+
+fn energy(kind: u8) -> usize {
+    10_usize.pow((kind - b'A') as u32)
+}
+
+impl Burrow {
+    pub fn parse(text: &str) -> Result<Burrow, String> {
+        let mut template: Vec<Vec<u8>> = text.lines().map(|s| s.bytes().collect()).collect();
+        let is_open = |c: u8| c == b'.' || c.is_ascii_uppercase();
+        let hall_row = template.iter().position(|row| row.iter().any(|&c| is_open(c)))
+            .ok_or("No open cells")?;
+        let hallway: Vec<(usize, usize)> = (0..template[hall_row].len())
+            .filter(|&x| is_open(template[hall_row][x])).map(|x| (hall_row, x)).collect();
+
+        let mut columns: Vec<usize> = Vec::new();
+        for row in &template[hall_row + 1..] {
+            for (x, &c) in row.iter().enumerate() {
+                if is_open(c) && !columns.contains(&x) { columns.push(x); }
+            }
+        }
+        columns.sort_unstable();
+        // Energies are powers of ten and 10^20 no longer fits in 64 bits.
+        if columns.len() > 20 { return Err("Too many rooms".to_string()); }
+
+        let mut rooms: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut doors: Vec<usize> = Vec::new();
+        for &x in &columns {
+            let door = hallway.iter().position(|&(_, hx)| hx == x)
+                .ok_or(format!("Room at column {} has no door", x))?;
+            let cells: Vec<(usize, usize)> = (hall_row + 1..template.len())
+                .take_while(|&y| template[y].get(x).is_some_and(|&c| is_open(c)))
+                .map(|y| (y, x)).collect();
+            if cells.len() + hall_row + 1 < template.len() &&
+               (hall_row + 1 + cells.len()..template.len()).any(
+                   |y| template[y].get(x).is_some_and(|&c| is_open(c))) {
+                return Err(format!("Room at column {} is not contiguous", x));
+            }
+            rooms.push(cells);
+            doors.push(door);
+        }
+
+        let mut offsets = vec![hallway.len()];
+        for room in &rooms { offsets.push(offsets.last().unwrap() + room.len()); }
+
+        let mut data = Vec::new();
+        for &(y, x) in hallway.iter().chain(rooms.iter().flatten()) {
+            let c = template[y][x];
+            if c.is_ascii_uppercase() && (c - b'A') as usize >= rooms.len() {
+                return Err(format!("Amphipod {} has no room", c as char));
+            }
+            data.push(if c == b'.' { 0 } else { c });
+            template[y][x] = b'.';
+        }
+        for (i, room) in rooms.iter().enumerate() {
+            let kind = b'A' + i as u8;
+            if data.iter().filter(|&&c| c == kind).count() != room.len() {
+                return Err(format!("Amphipods {} do not fill their room", kind as char));
+            }
+        }
+        Ok(Burrow { template, hallway, rooms, doors, offsets, start: State(data) })
+    }
+
+    fn index(&self, cell: Cell) -> usize {
+        match cell {
+            Cell::Hall(x) => x,
+            Cell::Room(r, level) => self.offsets[r] + level,
+        }
+    }
+
+    fn room<'a>(&self, state: &'a State, r: usize) -> &'a [u8] {
+        &state.0[self.offsets[r]..self.offsets[r + 1]]
+    }
+
+    fn owner(r: usize) -> u8 {
+        b'A' + r as u8
+    }
+
+    // Room contains only its own amphipods (or nothing).
+    fn is_ready(&self, state: &State, r: usize) -> bool {
+        self.room(state, r).iter().all(|&c| c == 0 || c == Burrow::owner(r))
+    }
+
+    fn is_door(&self, x: usize) -> bool {
+        self.doors.contains(&x)
+    }
+
+    fn hall_clear(&self, state: &State, from: usize, to: usize) -> bool {
+        let (a, b) = if from < to { (from + 1, to) } else { (to, from - 1) };
+        (a..=b).all(|x| state.0[x] == 0)
+    }
+
+    fn deepest_free(&self, state: &State, r: usize) -> usize {
+        self.room(state, r).iter().rposition(|&c| c == 0).unwrap()
+    }
+
+    pub fn is_final(&self, state: &State) -> bool {
+        (0..self.rooms.len()).all(|r| {
+            self.room(state, r).iter().all(|&c| c == Burrow::owner(r))
+        })
+    }
+
+    pub fn moves(&self, state: &State) -> Vec<Step> {
+        let mut result = Vec::new();
+        for (x, &kind) in state.0[..self.hallway.len()].iter().enumerate() {
+            if kind == 0 { continue; }
+            let t = (kind - b'A') as usize;
+            let door = self.doors[t];
+            if self.is_ready(state, t) && self.hall_clear(state, x, door) {
+                let level = self.deepest_free(state, t);
+                let dist = x.abs_diff(door) + level + 1;
+                result.push(Step {
+                    kind, from: Cell::Hall(x), to: Cell::Room(t, level), cost: dist * energy(kind) });
+            }
+        }
+        for r in 0..self.rooms.len() {
+            if self.is_ready(state, r) { continue; }
+            let room = self.room(state, r);
+            let level = room.iter().position(|&c| c != 0).unwrap();
+            let kind = room[level];
+            let from = Cell::Room(r, level);
+            let door = self.doors[r];
+            if state.0[door] != 0 { continue; }
+            let t = (kind - b'A') as usize;
+            if self.is_ready(state, t) && self.hall_clear(state, door, self.doors[t]) {
+                let target = self.deepest_free(state, t);
+                let dist = level + 1 + door.abs_diff(self.doors[t]) + target + 1;
+                result.push(Step { kind, from, to: Cell::Room(t, target), cost: dist * energy(kind) });
+            }
+            let left = (0..door).rev().take_while(|&x| state.0[x] == 0);
+            let right = (door + 1..self.hallway.len()).take_while(|&x| state.0[x] == 0);
+            for x in left.chain(right) {
+                if self.is_door(x) { continue; }
+                let dist = level + 1 + x.abs_diff(door);
+                result.push(Step { kind, from, to: Cell::Hall(x), cost: dist * energy(kind) });
+            }
+        }
+        result
+    }
+
+    pub fn apply(&self, state: &State, step: &Step) -> State {
+        let mut next = state.clone();
+        next.0[self.index(step.from)] = 0;
+        next.0[self.index(step.to)] = step.kind;
+        next
+    }
+
+    // Lower bound on the remaining cost: every amphipod outside its final
+    // place walks straight to its door and into the room, without waiting.
+    pub fn estimate(&self, state: &State) -> usize {
+        let mut total = 0;
+        let mut incoming = vec![0_usize; self.rooms.len()];
+        for (x, &kind) in state.0[..self.hallway.len()].iter().enumerate() {
+            if kind == 0 { continue; }
+            let t = (kind - b'A') as usize;
+            total += (x.abs_diff(self.doors[t]) + 1) * energy(kind);
+            incoming[t] += 1;
+        }
+        for r in 0..self.rooms.len() {
+            let room = self.room(state, r);
+            for (level, &kind) in room.iter().enumerate() {
+                if kind == 0 { continue; }
+                let t = (kind - b'A') as usize;
+                let dist = if t != r {
+                    level + 1 + self.doors[r].abs_diff(self.doors[t]) + 1
+                } else if room[level..].iter().any(|&c| c != kind) {
+                    level + 1 + 2 + 1
+                } else {
+                    continue;
+                };
+                total += dist * energy(kind);
+                incoming[t] += 1;
+            }
+        }
+        for (t, &n) in incoming.iter().enumerate() {
+            total += n * n.saturating_sub(1) / 2 * energy(Burrow::owner(t));
+        }
+        total
+    }
+
+    // A* search; returns the total energy and the moves in order.
+    pub fn solve(&self) -> Option<(usize, Vec<Step>)> {
+        let mut heap: BinaryHeap<Reverse<(usize, usize, State)>> = BinaryHeap::new();
+        let mut best: HashMap<State, (usize, Option<(State, Step)>)> = HashMap::new();
+        heap.push(Reverse((self.estimate(&self.start), 0, self.start.clone())));
+        best.insert(self.start.clone(), (0, None));
+
+        while let Some(Reverse((_, cost, state))) = heap.pop() {
+            if cost > best[&state].0 { continue; }
+            if self.is_final(&state) {
+                let mut steps = Vec::new();
+                let mut current = state;
+                while let Some((prev, step)) = best[&current].1.clone() {
+                    steps.push(step);
+                    current = prev;
+                }
+                steps.reverse();
+                return Some((cost, steps));
+            }
+            for step in self.moves(&state) {
+                let next = self.apply(&state, &step);
+                let total = cost + step.cost;
+                if best.get(&next).is_some_and(|&(c, _)| c <= total) { continue; }
+                heap.push(Reverse((total + self.estimate(&next), total, next.clone())));
+                best.insert(next, (total, Some((state.clone(), step))));
+            }
+        }
+        None
+    }
+
+    pub fn replay(&self, steps: &[Step]) -> Vec<State> {
+        let mut states = vec![self.start.clone()];
+        for step in steps {
+            states.push(self.apply(states.last().unwrap(), step));
+        }
+        states
+    }
+
+    pub fn render(&self, state: &State) -> String {
+        let mut grid = self.template.clone();
+        for (i, &(y, x)) in self.hallway.iter().chain(self.rooms.iter().flatten()).enumerate() {
+            if state.0[i] != 0 { grid[y][x] = state.0[i]; }
+        }
+        grid.into_iter().map(|row| String::from_utf8(row).unwrap() + "\n").collect()
+    }
+}
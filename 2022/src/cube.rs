@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+type Vector = [i32; 3];
+
+// Facings in scoring order; sides of a face use the same numbering.
+pub const RIGHT: usize = 0;
+pub const DOWN: usize = 1;
+pub const LEFT: usize = 2;
+pub const UP: usize = 3;
+
+const NAMES: [&str; 4] = ["right", "down", "left", "up"];
+const ARROWS: [u8; 4] = [b'>', b'v', b'<', b'^'];
+
+// Position on a face (local coordinates) and facing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursor {
+    pub face: usize,
+    pub x: usize,
+    pub y: usize,
+    pub facing: usize,
+}
+
+// Where leaving a face through one of its sides leads: the side of the
+// other face that is entered, and the clockwise quarter turns applied to
+// the facing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glue {
+    pub face: usize,
+    pub side: usize,
+    pub turns: usize,
+}
+
+// Face of the net folded onto the cube: block position on the map, the
+// outward normal, and the directions of increasing x and y.
+#[derive(Clone, Debug)]
+struct Face {
+    col: usize,
+    row: usize,
+    normal: Vector,
+    u: Vector,
+    v: Vector,
+}
+
+#[derive(Clone, Debug)]
+pub struct Net {
+    tiles: Vec<Vec<u8>>,
+    pub size: usize,
+    faces: Vec<Face>,
+}
+
+fn neg(a: Vector) -> Vector {
+    a.map(|v| -v)
+}
+
+fn dot(a: Vector, b: Vector) -> i32 {
+    (0..3).map(|i| a[i] * b[i]).sum()
+}
+
+impl Face {
+    fn towards(&self, side: usize) -> Vector {
+        match side {
+            RIGHT => self.u,
+            DOWN => self.v,
+            LEFT => neg(self.u),
+            UP => neg(self.v),
+            _ => panic!("unknown side {}", side),
+        }
+    }
+
+    // Frame of the face that lies across the given side once folded.
+    fn roll(&self, side: usize) -> (Vector, Vector, Vector) {
+        let (n, u, v) = (self.normal, self.u, self.v);
+        match side {
+            RIGHT => (u, neg(n), v),
+            DOWN => (v, u, neg(n)),
+            LEFT => (neg(u), n, v),
+            _ => (neg(v), u, n),
+        }
+    }
+}
+
+impl Cursor {
+    pub fn turn(self, clockwise: bool) -> Cursor {
+        Cursor { facing: (self.facing + if clockwise {1} else {3}) % 4, ..self }
+    }
+}
+
+impl Net {
+    // Folds any of the eleven cube nets, in any orientation; the face size
+    // follows from the number of tiles.
+    pub fn parse(text: &str) -> Result<Net, String> {
+        let tiles: Vec<Vec<u8>> = text.lines().map(|s| s.bytes().collect()).collect();
+        let count = tiles.iter().flatten().filter(|&&c| c != b' ').count();
+        let size = (1..).find(|n| 6 * n * n >= count).unwrap();
+        if 6 * size * size != count {
+            return Err(format!("{} tiles do not cover six square faces", count));
+        }
+        let at = |x: usize, y: usize| tiles.get(y).and_then(|r| r.get(x)).copied().unwrap_or(b' ');
+        let width = tiles.iter().map(|r| r.len()).max().unwrap_or(0);
+        let mut blocks = vec![];
+        for row in 0..tiles.len().div_ceil(size) {
+            for col in 0..width.div_ceil(size) {
+                let filled = (0..size * size)
+                    .filter(|i| at(col * size + i % size, row * size + i / size) != b' ')
+                    .count();
+                match filled {
+                    0 => (),
+                    n if n == size * size => blocks.push((col, row)),
+                    _ => return Err(format!("block ({}, {}) is partially filled", col, row)),
+                }
+            }
+        }
+
+        let mut faces = vec![Face {
+            col: blocks[0].0, row: blocks[0].1,
+            normal: [0, 0, 1], u: [1, 0, 0], v: [0, 1, 0],
+        }];
+        let mut queue = VecDeque::from([0]);
+        while let Some(i) = queue.pop_front() {
+            let (col, row) = (faces[i].col, faces[i].row);
+            let near = [(col + 1, row), (col, row + 1), (col.wrapping_sub(1), row), (col, row.wrapping_sub(1))];
+            for (side, pos) in near.into_iter().enumerate() {
+                if !blocks.contains(&pos) || faces.iter().any(|f| (f.col, f.row) == pos) { continue; }
+                let (normal, u, v) = faces[i].roll(side);
+                if faces.iter().any(|f| f.normal == normal) {
+                    return Err(format!("block ({}, {}) overlaps another face", pos.0, pos.1));
+                }
+                faces.push(Face { col: pos.0, row: pos.1, normal, u, v });
+                queue.push_back(faces.len() - 1);
+            }
+        }
+        if faces.len() != 6 {
+            return Err(format!("{} blocks are connected, expected six", faces.len()));
+        }
+        faces.sort_by_key(|f| (f.row, f.col));
+        Ok(Net { tiles, size, faces })
+    }
+
+    fn face_at(&self, normal: Vector) -> usize {
+        self.faces.iter().position(|f| f.normal == normal).unwrap()
+    }
+
+    pub fn glue(&self, face: usize, side: usize) -> Glue {
+        let from = &self.faces[face];
+        let to = self.face_at(from.towards(side));
+        let back = (0..4).find(|&s| self.faces[to].towards(s) == from.normal).unwrap();
+        Glue { face: to, side: back, turns: (back + 6 - side) % 4 }
+    }
+
+    // Edge-stitching table: every (face, side) with where it leads.
+    pub fn stitching(&self) -> Vec<(usize, usize, Glue)> {
+        (0..6).flat_map(|f| (0..4).map(move |s| (f, s, self.glue(f, s)))).collect()
+    }
+
+    // Top left open tile, facing right.
+    pub fn start(&self) -> Cursor {
+        let x = self.tiles[0].iter().position(|&c| c == b'.').unwrap();
+        let face = self.faces.iter().position(|f| f.row == 0 && f.col == x / self.size).unwrap();
+        Cursor { face, x: x % self.size, y: 0, facing: RIGHT }
+    }
+
+    // Map column and row of a cursor.
+    pub fn position(&self, c: &Cursor) -> (usize, usize) {
+        let f = &self.faces[c.face];
+        (f.col * self.size + c.x, f.row * self.size + c.y)
+    }
+
+    // Next tile in the facing direction, wrapping around the cube.
+    fn step(&self, c: &Cursor) -> Cursor {
+        let n = self.size;
+        let (x, y) = (c.x as i32, c.y as i32);
+        let (nx, ny) = match c.facing {
+            RIGHT => (x + 1, y),
+            DOWN => (x, y + 1),
+            LEFT => (x - 1, y),
+            _ => (x, y - 1),
+        };
+        if (0..n as i32).contains(&nx) && (0..n as i32).contains(&ny) {
+            return Cursor { x: nx as usize, y: ny as usize, ..*c };
+        }
+        // Doubled coordinates of the tile centre, with the cube spanning
+        // -n..n on every axis; crossing the edge rolls it onto the next face.
+        let f = &self.faces[c.face];
+        let (n, d) = (n as i32, f.towards(c.facing));
+        let p: Vector = [0, 1, 2].map(|i| {
+            n * f.normal[i] + (2 * x + 1 - n) * f.u[i] + (2 * y + 1 - n) * f.v[i]
+                + d[i] - f.normal[i]
+        });
+        let face = self.face_at(d);
+        let g = &self.faces[face];
+        let facing = (0..4).find(|&s| g.towards(s) == neg(f.normal)).unwrap();
+        let local = |axis: Vector| ((dot(p, axis) + n - 1) / 2) as usize;
+        Cursor { face, x: local(g.u), y: local(g.v), facing }
+    }
+
+    // Moves one tile forward unless a wall is in the way.
+    pub fn forward(&self, c: &Cursor) -> Option<Cursor> {
+        let next = self.step(c);
+        let (x, y) = self.position(&next);
+        if self.tiles[y][x] == b'#' {None} else {Some(next)}
+    }
+
+    // The map with the last facing on every visited tile.
+    pub fn render(&self, trail: &[Cursor]) -> String {
+        let mut tiles = self.tiles.clone();
+        for c in trail {
+            let (x, y) = self.position(c);
+            tiles[y][x] = ARROWS[c.facing];
+        }
+        tiles.into_iter().map(|r| String::from_utf8(r).unwrap() + "\n").collect()
+    }
+}
+
+impl fmt::Display for Net {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (face, side, glue) in self.stitching() {
+            writeln!(f, "{} {} -> {} {} ({} turns)", face, NAMES[side],
+                     glue.face, NAMES[glue.side], glue.turns)?;
+        }
+        fmt::Result::Ok(())
+    }
+}
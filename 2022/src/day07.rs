@@ -1,157 +1,31 @@
-use itertools::Itertools;
-use std::cell::RefCell;
-use std::iter;
-use std::rc::{Rc, Weak};
-
-#[derive(Debug)]
-enum NodeType {
-    Directory(RefCell<Vec<Rc<Node>>>),
-    File(usize),
-}
-
-#[derive(Debug)]
-struct Node {
-    name: String,
-    type_: NodeType,
-    parent: Weak<Node>,
-}
-
-impl Node {
-    fn is_dir(&self) -> bool {
-        matches!(self.type_, NodeType::Directory(_))
-    }
-
-    #[allow(dead_code)]
-    fn path(&self) -> String {
-        let mut arr: Vec<Rc<Node>> = Vec::new();
-        let mut cur = self.parent.clone();
-        while let Some(ptr) = cur.upgrade() {
-            cur = ptr.parent.clone();
-            arr.push(ptr);
-        }
-        arr.iter().rev().map(|x| &x.name)
-            .chain(iter::once(&self.name)).join("/")
-    }
-
-    fn size(&self) -> usize {
-        match &self.type_ {
-            NodeType::File(size) => *size,
-            NodeType::Directory(children) => {
-                children.borrow().iter().map(|x| x.size()).sum()
-            }
-        }
-    }
-}
-
-struct FileSystem {
-    root: Rc<Node>,
-}
-
-impl FileSystem {
-    fn from(input: &str) -> FileSystem {
-        let root = Rc::new(Node {
-            name: String::new(),
-            type_: NodeType::Directory(RefCell::new(Vec::new())),
-            parent: Weak::new(),
-        });
-        let mut current = root.clone();
-        for line in input.lines() {
-            let tokens: Vec<&str> = line.split_whitespace().collect();
-            match tokens[..] {
-                ["$", "cd", name] => current = match name {
-                    "/" => root.clone(),
-                    ".." => current.parent.upgrade().unwrap(),
-                    _ => if let NodeType::Directory(children) = &current.type_ {
-                        children.borrow().iter().filter(
-                            |x| x.name == name).next().unwrap().clone()
-                    } else {
-                        panic!("Unexpected state")
-                    }
-                },
-                ["$", "ls"] => (),
-                [dir_or_size, name] => {
-                    let node = Rc::new(Node {
-                        name: String::from(name),
-                        type_: if dir_or_size == "dir" {
-                            NodeType::Directory(RefCell::new(Vec::new()))
-                        } else {
-                            NodeType::File(dir_or_size.parse().unwrap())
-                        },
-                        parent: Rc::downgrade(&current),
-                    });
-                    if let NodeType::Directory(children) = &current.type_ {
-                        children.borrow_mut().push(node);
-                    }
-                },
-                _ => panic!("Incorrect format")
-            }
-        }
-        FileSystem { root }
-    }
-
-    fn iter(&self) -> FileSystemIterator {
-        FileSystemIterator {
-            cur: Rc::downgrade(&self.root),
-            pos: vec![0],
-        }
-    }
-
-    fn collect_dirs(&self) -> Vec<(Rc<Node>, usize)> {
-        let mut result: Vec<(Rc<Node>, usize)> = Vec::new();
-        for node in self.iter() {
-            if node.is_dir() {
-                let size = node.size();
-                result.push((node, size));
-            }
-        }
-        result
-    }
-}
-
-struct FileSystemIterator {
-    cur: Weak<Node>,
-    pos: Vec<usize>,
-}
-
-impl Iterator for FileSystemIterator {
-    type Item = Rc<Node>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Some(index) = self.pos.pop() {
-            let node = self.cur.upgrade().unwrap();
-            if let NodeType::Directory(children) = &node.type_ {
-                match children.borrow().get(index) {
-                    Some(child) => {
-                        self.pos.push(index + 1);
-                        if child.is_dir() {
-                            self.cur = Rc::downgrade(&child);
-                            self.pos.push(0);
-                        };
-                        return Some(child.clone());
-                    },
-                    None => {
-                        self.cur = node.parent.clone();
-                    }
-                }
-            };
-        }
-        None
-    }
-}
+use crate::vfs::{FileSystem, ROOT};
+use std::env;
 
 pub fn run(content: &str) {
-    let fs = FileSystem::from(content);
-    let mut sizes: Vec<usize> = fs.collect_dirs().iter().map(|x| x.1).collect();
+    let (fs, issues) = FileSystem::replay(content);
+    for issue in &issues {
+        eprintln!("{}", issue);
+    }
+    let small: usize = fs.find(ROOT, |node, size| node.is_dir() && size <= 100_000)
+        .into_iter().map(|id| fs.size(id)).sum();
+    let mut sizes: Vec<usize> = fs.du(ROOT).into_iter().map(|x| x.1).collect();
     sizes.sort();
 
-    let small: usize = sizes.iter().filter(|&&x| x <= 100_000).sum();
-    let unused = 70_000_000 - fs.root.size();
-    let remove = sizes.into_iter().filter(|&x| x + unused >= 30_000_000).next();
+    let unused = 70_000_000 - fs.size(ROOT);
+    let remove = sizes.into_iter().find(|&x| x + unused >= 30_000_000);
     println!("{} {}", small, remove.unwrap());
+    if env::args().any(|a| a == "--tree") {
+        print!("{}", fs.tree(ROOT));
+    }
+    if env::args().any(|a| a == "--json") {
+        println!("{}", fs.to_json(ROOT));
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::vfs::{FileSystem, ROOT};
+
     fn example() -> &'static str { r#"
 $ cd /
 $ ls
@@ -181,8 +55,80 @@ $ ls
 
     #[test]
     pub fn dirs() {
-        let fs = super::FileSystem::from(example());
-        let sizes: Vec<usize> = fs.collect_dirs().iter().map(|x| x.1).collect();
-        assert_eq!(sizes, vec![94853, 584, 24933642]);
+        let fs = FileSystem::from(example());
+        assert_eq!(fs.du(ROOT), vec![
+            (String::from("/d"), 24933642),
+            (String::from("/a/e"), 584),
+            (String::from("/a"), 94853),
+            (String::from("/"), 48381165),
+        ]);
+    }
+
+    #[test]
+    pub fn tree() {
+        let fs = FileSystem::from(example());
+        assert_eq!(fs.tree(ROOT), "\
+- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+");
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!(fs.to_json(e),
+                   r#"{"name":"e","type":"dir","size":584,"children":[{"name":"i","type":"file","size":584}]}"#);
+    }
+
+    #[test]
+    pub fn find() {
+        let fs = FileSystem::from(example());
+        let paths = |ids: Vec<usize>| ids.into_iter().map(|id| fs.path(id)).collect::<Vec<_>>();
+        assert_eq!(paths(fs.find(ROOT, |n, size| n.is_dir() && size <= 100_000)), vec!["/a", "/a/e"]);
+        assert_eq!(paths(fs.find(ROOT, |n, _| n.name.starts_with("d."))), vec!["/d/d.ext", "/d/d.log"]);
+        let d = fs.lookup("/d").unwrap();
+        assert_eq!(paths(fs.find(d, |_, size| size > 7_500_000)), vec!["/d", "/d/d.log"]);
+    }
+
+    #[test]
+    pub fn issues() {
+        let transcript = example().to_string() + "\n\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+dir d
+9 z
+$ cd a
+$ ls
+dir e
+29116 f
+2558 g
+$ cd x
+$ cd /
+$ cd ..
+12 y";
+        let (fs, issues) = FileSystem::replay(&transcript);
+        let messages = issues.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "line 25: /c.dat disappeared from the listing",
+            "line 29: /z was not listed before",
+            "line 31: /a/h.lst disappeared from the listing",
+            "line 34: /a/g was a file of size 2557",
+            "line 35: cd into unlisted directory /a/x",
+            "line 37: cd .. at the root",
+            "line 38: output outside of ls: 12 y",
+        ]);
+        assert_eq!(fs.size(fs.lookup("/a").unwrap()), 584 + 29116 + 2558);
+        assert_eq!(fs.lookup("/a/h.lst"), None);
+        assert_eq!(fs.size(ROOT), 14848514 + 9 + 24933642 + 584 + 29116 + 2558);
     }
 }
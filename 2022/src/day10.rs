@@ -1,3 +1,4 @@
+use crate::ocr;
 
 #[derive(Clone, Copy, Debug)]
 enum Operation {
@@ -23,6 +24,16 @@ impl Program {
         Program { ops }
     }
 
+    // CRT rows drawn by the sprite, 40 pixels each.
+    fn screen(&self) -> String {
+        self.iter().enumerate().map(|(k, v)| {
+            let x = (k % 40) as i32;
+            let fill = (v-1..=v+1).contains(&x);
+            let pixel = if fill {'#'} else {'.'};
+            if x == 39 {format!("{}\n", pixel)} else {pixel.to_string()}
+        }).collect()
+    }
+
     fn iter(&self) -> ProgramIter<'_> {
         ProgramIter {
             ops: &self.ops[..],
@@ -63,13 +74,10 @@ pub fn run(content: &str) {
     let inst = Program::from(content);
     let signal: i32 = inst.iter().enumerate().map(|(k, v)|
         (k as i32 + 1) * v).skip(19).step_by(40).take(6).sum();
-    println!("{}", signal);
-
-    for (k, v) in inst.iter().enumerate() {
-        let x = (k % 40) as i32;
-        let fill = (v-1..=v+1).contains(&x);
-        print!("{}", if fill {'#'} else {'.'});
-        if x == 39 { println!(); }
+    let screen = inst.screen();
+    match ocr::read_bitmap(&screen, '#') {
+        Some(text) => println!("{} {}", signal, text),
+        None => print!("{}\n{}", signal, screen),
     }
 }
 
@@ -81,4 +89,30 @@ mod tests {
         let data: Vec<i32> = inst.iter().collect();
         assert_eq!(data, [1, 1, 1, 4, 4]);
     }
+
+    #[test]
+    pub fn screen() {
+        // Every addx spans two pixels with the same sprite position, and
+        // sets the position used for the next two.
+        let letter = ["####", "#...", "###.", "#...", "#...", "####"];
+        let mut sprite = vec![];
+        for row in letter {
+            let on = |x: usize| row.as_bytes().get(x) == Some(&b'#');
+            for x in (0..40).step_by(2) {
+                sprite.push(match (on(x), on(x + 1)) {
+                    (true, true) => x as i32 + 1,
+                    (true, false) => x as i32 - 1,
+                    (false, true) => x as i32 + 2,
+                    (false, false) => -10,
+                });
+            }
+        }
+        assert_eq!(sprite[0], 1);
+        let ops: Vec<String> = sprite.windows(2).map(|w| format!("addx {}", w[1] - w[0]))
+            .chain([String::from("noop")]).collect();
+        let inst = super::Program::from(&ops.join("\n"));
+        let screen = inst.screen();
+        assert_eq!(screen.lines().nth(2), Some("###....................................."));
+        assert_eq!(super::ocr::read_bitmap(&screen, '#'), Some(String::from("E")));
+    }
 }
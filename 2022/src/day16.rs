@@ -1,5 +1,6 @@
 use regex::Regex;
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
+use std::env;
 
 #[derive(Debug)]
 struct Valve {
@@ -30,6 +31,29 @@ struct Volcano {
     lookup: HashMap<String, usize>,
 }
 
+// Valves with non-zero flow and travel times between them.
+#[derive(Debug)]
+struct Network {
+    valves: Vec<usize>,
+    start: Vec<u32>,
+    dist: Vec<Vec<u32>>,
+}
+
+// Best release for one agent opening exactly the valves in `mask`, with the
+// (valve, minute) openings that achieve it.
+#[derive(Clone, Debug)]
+struct Route {
+    mask: u64,
+    score: u32,
+    schedule: Vec<(usize, u32)>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Plan {
+    score: u32,
+    schedules: Vec<Vec<(String, u32)>>,
+}
+
 impl Volcano {
     fn from(input: &str) -> Volcano {
         let nodes: Vec<Valve> = input.lines().map(Valve::from).collect();
@@ -45,60 +69,119 @@ impl Volcano {
         ).sum()
     }
 
-    fn traverse(&self, limit: u32, init_mask: u64) -> (u64, u32) {
-        type State = (usize, u64, u32, u32);  // index, mask, score, step
-        let init_pos = *self.lookup.get("AA").unwrap();
-        let mut queue = VecDeque::<State>::from([(init_pos, init_mask, 0, 0)]);
-        let mut best = HashMap::<(usize, u64), u32>::new();
-
-        while let Some((index, mask, score, step)) = queue.pop_front() {
-            if step >= limit { break; }
-            if let Some(&n) = best.get(&(index, mask)) {
-                if score <= n { continue; }
-            }
-
-            let valve = &self.nodes[index];
-            if mask & (1 << index) == 0 && valve.flow > 0 {
-                let new_mask = mask | (1 << index);
-                let new_score = score + valve.flow * (limit - step - 1);
-                queue.push_back((index, new_mask, new_score, step + 1));
-            }
+    // Floyd-Warshall over all tunnels, kept only for the useful valves.
+    fn network(&self) -> Network {
+        let n = self.nodes.len();
+        let mut dist = vec![vec![u32::MAX / 2; n]; n];
+        for (i, valve) in self.nodes.iter().enumerate() {
+            dist[i][i] = 0;
             for dest in &valve.tunnels {
-                let new_index = *self.lookup.get(dest).unwrap();
-                queue.push_back((new_index, mask, score, step + 1));
+                dist[i][self.lookup[dest]] = 1;
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    dist[i][j] = dist[i][j].min(dist[i][k] + dist[k][j]);
+                }
             }
-            best.insert((index, mask), score);
         }
-        let mut result: (u64, u32) = (0, 0);
-        for ((_, k), v) in best {
-            if v > result.1 { result = (k ^ init_mask, v); }
+        let valves: Vec<usize> = (0..n).filter(|&i| self.nodes[i].flow > 0).collect();
+        assert!(valves.len() <= 64, "too many valves");
+        let init_pos = self.lookup["AA"];
+        let start = valves.iter().map(|&v| dist[init_pos][v]).collect();
+        let dist = valves.iter().map(|&a| valves.iter().map(|&b| dist[a][b]).collect()).collect();
+        Network { valves, start, dist }
+    }
+
+    // Depth-first search over opening orders, recording the best route for
+    // every set of opened valves.
+    fn explore(&self, net: &Network, budget: u32, pos: Option<usize>, left: u32,
+               route: &mut Route, best: &mut HashMap<u64, Route>) {
+        if best.get(&route.mask).is_none_or(|r| route.score > r.score) {
+            best.insert(route.mask, route.clone());
         }
+        for next in 0..net.valves.len() {
+            let d = match pos { Some(p) => net.dist[p][next], None => net.start[next] };
+            if route.mask & (1 << next) != 0 || d + 1 >= left { continue; }
+            let rest = left - d - 1;
+            let prev = (route.mask, route.score);
+            route.mask |= 1 << next;
+            route.score += self.nodes[net.valves[next]].flow * rest;
+            route.schedule.push((next, budget - rest));
+            self.explore(net, budget, Some(next), rest, route, best);
+            route.schedule.pop();
+            (route.mask, route.score) = prev;
+        }
+    }
+
+    fn routes(&self, net: &Network, budget: u32) -> Vec<Route> {
+        let mut best = HashMap::new();
+        let mut route = Route { mask: 0, score: 0, schedule: vec![] };
+        self.explore(net, budget, None, budget, &mut route, &mut best);
+        let mut result: Vec<Route> = best.into_values().collect();
+        result.sort_by_key(|r| (std::cmp::Reverse(r.score), r.mask));
         result
     }
 
+    // Best total release for agents with the given time budgets, all
+    // starting at AA and never opening the same valve twice.
+    fn plan(&self, budgets: &[u32]) -> Plan {
+        let net = self.network();
+        let routes: Vec<Vec<Route>> = budgets.iter().map(|&b| self.routes(&net, b)).collect();
+        // Best choice of routes for every union of opened valves so far.
+        let mut combined = HashMap::from([(0_u64, (0_u32, vec![]))]);
+        for (k, agent) in routes.iter().enumerate() {
+            let last = k + 1 == routes.len();
+            let mut next = HashMap::<u64, (u32, Vec<usize>)>::new();
+            for (mask, (score, chosen)) in &combined {
+                // Routes are sorted by score, so the last agent stops at the
+                // first one that fits.
+                for (i, r) in agent.iter().enumerate() {
+                    if r.mask & mask != 0 { continue; }
+                    let key = if last {0} else {mask | r.mask};
+                    let total = score + r.score;
+                    if next.get(&key).is_none_or(|(s, _)| total > *s) {
+                        let mut chosen = chosen.clone();
+                        chosen.push(i);
+                        next.insert(key, (total, chosen));
+                    }
+                    if last { break; }
+                }
+            }
+            combined = next;
+        }
+        let (score, chosen) = combined.into_values().max_by_key(|(s, _)| *s).unwrap();
+        let schedules = chosen.iter().enumerate().map(|(k, &i)| {
+            routes[k][i].schedule.iter().map(|&(v, minute)| {
+                (self.nodes[net.valves[v]].name.clone(), minute)
+            }).collect()
+        }).collect();
+        Plan { score, schedules }
+    }
+
     fn traverse_one(&self, limit: u32) -> u32 {
-        self.traverse(limit, 0).1
+        self.plan(&[limit]).score
     }
 
     fn traverse_two(&self, limit: u32) -> u32 {
-        let mut result: u32 = 0;
-        let p: Vec<usize> = self.nodes.iter().enumerate().filter_map(
-            |(k, v)| if v.flow > 0 {Some(k)} else {None}).collect();
-        for m in 0..(1 << p.len() - 1) {
-            let split_mask: u64 = (0..p.len()).map(
-                |i| if m & (1 << i) != 0 {1 << p[i]} else {0}).sum();
-            let (_, score1) = self.traverse(limit, split_mask);
-            let (_, score2) = self.traverse(limit, !split_mask);
-            let score = score1 + score2;
-            if score > result { result = score; }
-        }
-        result
+        self.plan(&[limit, limit]).score
     }
 }
 
 pub fn run(content: &str) {
     let inst = Volcano::from(content);
     println!("{} {}", inst.traverse_one(30), inst.traverse_two(26));
+    if env::args().any(|a| a == "--trace") {
+        for budgets in [&[30][..], &[26, 26]] {
+            let plan = inst.plan(budgets);
+            for (k, schedule) in plan.schedules.iter().enumerate() {
+                let opened = schedule.iter().map(|(v, m)| format!("{} at {}", v, m))
+                    .collect::<Vec<_>>();
+                println!("agent {} of {}: {}", k + 1, budgets.len(), opened.join(", "));
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -122,4 +205,33 @@ mod tests {
         assert_eq!(inst.traverse_one(30), 1651);
         assert_eq!(inst.traverse_two(26), 1707);
     }
+
+    #[test]
+    pub fn schedules() {
+        let inst = super::Volcano::from(example());
+        let owned = |a: &[(&str, u32)]| {
+            a.iter().map(|&(s, m)| (s.to_string(), m)).collect::<Vec<_>>()
+        };
+        let one = inst.plan(&[30]);
+        assert_eq!(one.schedules, [owned(&[
+            ("DD", 2), ("BB", 5), ("JJ", 9), ("HH", 17), ("EE", 21), ("CC", 24)
+        ])]);
+
+        let two = inst.plan(&[26, 26]);
+        let mut schedules = two.schedules.clone();
+        schedules.sort();
+        assert_eq!(schedules, [
+            owned(&[("DD", 2), ("HH", 7), ("EE", 11)]),
+            owned(&[("JJ", 3), ("BB", 7), ("CC", 9)]),
+        ]);
+
+        let budgets = [20, 10, 5];
+        let three = inst.plan(&budgets);
+        let released: u32 = three.schedules.iter().zip(budgets).map(|(s, b)| {
+            s.iter().map(|(v, m)| inst.nodes[inst.lookup[v]].flow * (b - m)).sum::<u32>()
+        }).sum();
+        assert_eq!(released, three.score);
+        assert!(three.score >= inst.plan(&[20, 10]).score);
+        assert_eq!(inst.plan(&[1]).score, 0);
+    }
 }
@@ -1,5 +1,6 @@
-use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
+use std::hash::Hash;
 
 const FIG_1: &str = "####";
 const FIG_2: &str = ".#.\n###\n.#.";
@@ -8,21 +9,20 @@ const FIG_4: &str = "#\n#\n#\n#";
 const FIG_5: &str = "##\n##";
 const STEPS: usize = 1_000_000_000_000;
 
+// Rows from the bottom up, bit i of a row is column i.
 #[derive(Clone)]
 struct Grid {
-    data: Vec<Vec<u8>>,
+    data: Vec<u8>,
+    width: usize,
 }
 
 impl Grid {
     fn from(input: &str) -> Grid {
-        let data = input.lines().map(|s| s.chars().map(|c| match c {
-            '.' => 0, '#' => 1, _ => panic!()
-        }).collect()).collect();
-        Grid { data }
-    }
-
-    fn width(&self) -> usize {
-        self.data[0].len()
+        let data = input.lines().map(|s| s.chars().enumerate().map(|(i, c)| match c {
+            '.' => 0, '#' => 1 << i, _ => panic!()
+        }).sum()).collect();
+        let width = input.lines().map(|s| s.len()).max().unwrap();
+        Grid { data, width }
     }
 
     fn height(&self) -> usize {
@@ -33,8 +33,8 @@ impl Grid {
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for line in self.data.iter().rev() {
-            writeln!(f, "{}", line.iter().map(|&x|
-                if x != 0 {'#'} else {'.'}).collect::<String>())?;
+            writeln!(f, "{}", (0..self.width).map(|i|
+                if line & (1 << i) != 0 {'#'} else {'.'}).collect::<String>())?;
         }
         fmt::Result::Ok(())
     }
@@ -43,18 +43,41 @@ impl fmt::Display for Grid {
 #[derive(Clone)]
 struct Tower {
     grid: Grid,
-    width: usize,
+    shapes: Vec<Grid>,
     jets: String,
     index: usize,
+    count: usize,
+}
+
+// Value after `steps` steps of a process that eventually repeats. `advance`
+// performs one step and returns the key of the new state and the value so
+// far; once a key repeats, the gain of one cycle is added arithmetically.
+fn fast_forward<K, F>(steps: usize, mut advance: F) -> usize
+where K: Eq + Hash, F: FnMut() -> (K, usize) {
+    let mut seen = HashMap::<K, usize>::new();
+    let mut values = vec![0];
+    for i in 1..=steps {
+        let (key, value) = advance();
+        values.push(value);
+        if let Some(&start) = seen.get(&key) {
+            let (period, rest) = (i - start, steps - i);
+            let gain = value - values[start];
+            return value + rest / period * gain + values[start + rest % period] - values[start];
+        }
+        seen.insert(key, i);
+    }
+    values[steps]
 }
 
 impl Tower {
     fn create(jets: String, width: usize) -> Tower {
+        assert!(width <= 8, "rows are stored as bytes");
         Tower {
-            grid: Grid { data: vec![] },
-            width,
+            grid: Grid { data: vec![], width },
+            shapes: Tower::get_shapes(),
             jets,
             index: 0,
+            count: 0,
         }
     }
 
@@ -64,80 +87,76 @@ impl Tower {
 
     fn is_valid(&self, shape: &Grid, position: (i32, i32)) -> bool {
         let (x, y) = position;
-        if y < 0 || x < 0 || x as usize > self.width - shape.width() {
+        if y < 0 || x < 0 || x as usize + shape.width > self.grid.width {
             return false;
         }
-        if y as usize >= self.grid.height() {
-            return true;
-        }
-        (0..cmp::min(self.grid.height() - y as usize, shape.height())).all(|i| {
-            self.grid.data[y as usize + i].iter().skip(x as usize)
-                .zip(shape.data[i].iter()).all(|(a, b)| a + b <= 1)
+        shape.data.iter().enumerate().all(|(i, line)| {
+            let row = self.grid.data.get(y as usize + i).unwrap_or(&0);
+            row & (line << x) == 0
         })
     }
 
-    fn step(&self, shape: &Grid, position: (i32, i32), delta: i32) -> (i32, i32) {
-        let (mut x, mut y) = position;
-        if self.is_valid(shape, (x + delta, y)) { x += delta; }
-        if self.is_valid(shape, (x, y - 1)) { y -= 1; }
-        (x, y)
-    }
-
-    fn drop(&mut self, shape: &Grid) {
+    fn drop(&mut self) {
+        let shape = &self.shapes[self.count % self.shapes.len()];
         let mut pos: (i32, i32) = (2, self.grid.height() as i32 + 3);
         loop {
-            let prev = pos.1;
             let delta: i32 = match self.jets.as_bytes()[self.index] {
-                60 => -1,  // '<'
-                62 => 1,   // '>'
+                b'<' => -1,
+                b'>' => 1,
                 _ => panic!()
             };
-            pos = self.step(shape, pos, delta);
             self.index = (self.index + 1) % self.jets.len();
-            if pos.1 == prev { break; }
+            if self.is_valid(shape, (pos.0 + delta, pos.1)) { pos.0 += delta; }
+            if !self.is_valid(shape, (pos.0, pos.1 - 1)) { break; }
+            pos.1 -= 1;
         }
-        shape.data.iter().enumerate().for_each(|(i, line)| {
+        for (i, line) in shape.data.iter().enumerate() {
             let y = pos.1 as usize + i;
             if y >= self.grid.height() {
-                self.grid.data.push(vec![0; self.width]);
+                self.grid.data.push(0);
             }
-            line.iter().enumerate().for_each(|(j, value)| {
-                if *value != 0 {
-                    let x = pos.0 as usize + j;
-                    self.grid.data[y][x] = 1;
+            self.grid.data[y] |= line << pos.0;
+        }
+        self.count += 1;
+    }
+
+    // Free cells reachable from above the tower, one mask per row from the
+    // top down; nothing below them can affect later rocks.
+    fn surface(&self) -> Vec<u8> {
+        let (width, height) = (self.grid.width as i32, self.grid.height() as i32);
+        let free = |x: i32, y: i32| {
+            x >= 0 && x < width && y >= 0 && (y >= height || self.grid.data[y as usize] & (1 << x) == 0)
+        };
+        let mut visited = (0..width).map(|x| (x, height)).collect::<HashSet<_>>();
+        let mut queue = visited.iter().copied().collect::<VecDeque<_>>();
+        let mut result = vec![0_u8];
+        while let Some((x, y)) = queue.pop_front() {
+            let depth = (height - y) as usize;
+            if depth >= result.len() { result.resize(depth + 1, 0); }
+            result[depth] |= 1 << x;
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1)] {
+                if free(nx, ny) && visited.insert((nx, ny)) {
+                    queue.push_back((nx, ny));
                 }
-            });
-        });
+            }
+        }
+        result
     }
 
     fn simulate(&mut self, steps: usize) -> usize {
-        let shapes = Tower::get_shapes();
-        for i in 0..steps {
-            let shape = &shapes[i % shapes.len()];
-            self.drop(shape);
+        for _ in 0..steps {
+            self.drop();
         }
         self.grid.height()
     }
 
+    // Height after `steps` rocks, skipping over repeated states.
     fn solve(&mut self, steps: usize) -> usize {
-        let block = Tower::get_shapes().len() * self.jets.len();
-        let mut deltas = Vec::<usize>::new();
-        const N: usize = 3;
-        loop {
-            let prev = self.grid.height();
-            let size = self.simulate(block);
-            deltas.push(size - prev);
-            for n in 1..=(deltas.len() / N) {
-                let head = deltas.len() - n * N;
-                let s = &deltas[head..];
-                if (0..n).all(|i| (1..N).all(|j| s[n * j + i] == s[i])) {
-                    let rsize: usize = deltas[head..head + n].iter().sum();
-                    let m = (steps - head * block) / (n * block);
-                    let x = steps - (head + m * n) * block;
-                    return (m - N) * rsize + self.simulate(x);
-                }
-            }
-        }
+        fast_forward(steps, || {
+            self.drop();
+            let key = (self.count % self.shapes.len(), self.index, self.surface());
+            (key, self.grid.height())
+        })
     }
 }
 
@@ -156,4 +175,24 @@ mod tests {
         assert_eq!(tower.clone().simulate(2022), 3068);
         assert_eq!(tower.clone().solve(super::STEPS), 1_514_285_714_288);
     }
+
+    #[test]
+    pub fn fast_forward() {
+        let jets = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+        let tower = super::Tower::create(String::from(jets), 7);
+        for steps in [0, 1, 7, 100, 999, 2022, 5000] {
+            assert_eq!(tower.clone().solve(steps), tower.clone().simulate(steps));
+        }
+        let mut small = tower.clone();
+        small.simulate(3);
+        assert_eq!(small.grid.to_string(), "\
+..#....
+..#....
+####...
+..###..
+...#...
+..####.
+");
+        assert_eq!(small.surface()[..3], [0b1111111, 0b1111011, 0b1111011]);
+    }
 }
@@ -1,19 +1,30 @@
 use regex::Regex;
-use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::thread;
 
 type Ore = u16;
 type Clay = u16;
 type Obsidian = u16;
-type Geode = u16;
-type Resources = (Ore, Clay, Obsidian, Geode);
+type Resources = [u16; 4];
 
-#[derive(Debug)]
+const NAMES: [&str; 4] = ["ore", "clay", "obsidian", "geode"];
+const GEODE: usize = 3;
+
+#[derive(Clone, Debug)]
 struct State {
-    step: u16,
+    left: u16,
     resources: Resources,
     robots: Resources,
 }
 
+// Robots (by resource index) in the order they are built, with the minute
+// their construction starts.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Plan {
+    geodes: u16,
+    builds: Vec<(u16, usize)>,
+}
+
 #[derive(Debug)]
 struct Blueprint {
     id: u16,
@@ -43,56 +54,121 @@ impl Blueprint {
         }
     }
 
-    fn mine(&self, limit: u16) -> u16 {
-        let ore_max = [self.ore_cost, self.clay_cost, self.obsidian_cost.0,
-                       self.geode_cost.0].into_iter().max().unwrap();
-        let mut queue: VecDeque<State> = VecDeque::from([
-            State { step: 0, resources: (0, 0, 0, 0), robots: (1, 0, 0, 0) }
-        ]);
-        let mut visited: HashSet<(Resources, Resources)> = HashSet::new();
-        let mut result: u16 = 0;
-
-        while let Some(State {step, resources: r, robots: p}) = queue.pop_front() {
-            if step >= limit { break; }
-            let mut add_state = |resources, robots| {
-                if !visited.contains(&(resources, robots)) {
-                    queue.push_back(State { step: step + 1, resources, robots });
-                    visited.insert((resources, robots));
-                    if resources.3 > result { result = resources.3; }
-                }
-            };
-            let x = (r.0 + p.0, r.1 + p.1, r.2 + p.2, r.3 + p.3);
-            if r.0 <= ore_max {
-                add_state(x, p);
-            }
-            if r.0 >= self.ore_cost {
-                add_state((x.0 - self.ore_cost, x.1, x.2, x.3),
-                          (p.0 + 1, p.1, p.2, p.3));
-            }
-            if r.0 >= self.clay_cost {
-                add_state((x.0 - self.clay_cost, x.1, x.2, x.3),
-                          (p.0, p.1 + 1, p.2, p.3));
-            }
-            if r.0 >= self.obsidian_cost.0 && r.1 >= self.obsidian_cost.1 {
-                add_state((x.0 - self.obsidian_cost.0,
-                           x.1 - self.obsidian_cost.1, x.2, x.3),
-                          (p.0, p.1, p.2 + 1, p.3));
-            }
-            if r.0 >= self.geode_cost.0 && r.2 >= self.geode_cost.1 {
-                add_state((x.0 - self.geode_cost.0, x.1,
-                           x.2 - self.geode_cost.1, x.3),
-                          (p.0, p.1, p.2, p.3 + 1));
+    fn costs(&self) -> [Resources; 4] {
+        [
+            [self.ore_cost, 0, 0, 0],
+            [self.clay_cost, 0, 0, 0],
+            [self.obsidian_cost.0, self.obsidian_cost.1, 0, 0],
+            [self.geode_cost.0, 0, self.geode_cost.1, 0],
+        ]
+    }
+
+    // No point in producing more of a resource per minute than a single
+    // robot can consume.
+    fn caps(&self) -> Resources {
+        let costs = self.costs();
+        let mut caps = [u16::MAX; 4];
+        for (k, cap) in caps.iter_mut().enumerate().take(GEODE) {
+            *cap = costs.iter().map(|c| c[k]).max().unwrap();
+        }
+        caps
+    }
+
+    // Minutes until a robot of the given kind is ready, if it is worth it.
+    fn wait(&self, state: &State, cost: &Resources) -> Option<u16> {
+        let mut wait = 0;
+        let stock = state.resources.iter().zip(&state.robots);
+        for (&need, (&have, &robots)) in cost.iter().zip(stock).take(GEODE) {
+            if need <= have { continue; }
+            if robots == 0 { return None; }
+            wait = wait.max((need - have).div_ceil(robots));
+        }
+        if wait + 1 < state.left {Some(wait + 1)} else {None}
+    }
+
+    // Branch on the next robot to build, pruning with an optimistic bound
+    // that assumes a new geode robot every remaining minute.
+    fn search(&self, state: &State, limit: u16, builds: &mut Vec<(u16, usize)>, best: &mut Plan) {
+        let left = state.left;
+        let geodes = state.resources[GEODE] + state.robots[GEODE] * left;
+        if geodes > best.geodes {
+            *best = Plan { geodes, builds: builds.clone() };
+        }
+        if geodes + left * left.saturating_sub(1) / 2 <= best.geodes { return; }
+        let (costs, caps) = (self.costs(), self.caps());
+        for kind in (0..4).rev() {
+            if state.robots[kind] >= caps[kind] { continue; }
+            let Some(t) = self.wait(state, &costs[kind]) else { continue };
+            let mut next = state.clone();
+            next.left -= t;
+            for (k, r) in next.resources.iter_mut().enumerate() {
+                *r = *r + state.robots[k] * t - costs[kind][k];
             }
+            next.robots[kind] += 1;
+            builds.push((limit - left + t, kind));
+            self.search(&next, limit, builds, best);
+            builds.pop();
         }
-        result
     }
+
+    fn optimize(&self, limit: u16) -> Plan {
+        let init = State { left: limit, resources: [0; 4], robots: [1, 0, 0, 0] };
+        let mut best = Plan::default();
+        self.search(&init, limit, &mut vec![], &mut best);
+        best
+    }
+
+    #[allow(dead_code)]
+    fn mine(&self, limit: u16) -> u16 {
+        self.optimize(limit).geodes
+    }
+
+    // One line per minute: the robot started, if any, and the stock at the
+    // end of the minute.
+    fn timeline(&self, limit: u16, plan: &Plan) -> Vec<String> {
+        let costs = self.costs();
+        let mut resources = [0_u16; 4];
+        let mut robots = [1_u16, 0, 0, 0];
+        (1..=limit).map(|minute| {
+            let build = plan.builds.iter().find(|b| b.0 == minute).map(|b| b.1);
+            if let Some(kind) = build {
+                for k in 0..4 { resources[k] -= costs[kind][k]; }
+            }
+            for k in 0..4 { resources[k] += robots[k]; }
+            let action = match build {
+                Some(kind) => {
+                    robots[kind] += 1;
+                    format!("build {} robot", NAMES[kind])
+                },
+                None => String::from("wait"),
+            };
+            let stock = resources.iter().map(|r| r.to_string()).collect::<Vec<_>>().join("/");
+            format!("{}: {}, stock {}", minute, action, stock)
+        }).collect()
+    }
+}
+
+// Evaluates every blueprint on its own thread.
+fn optimize_all(bps: &[Blueprint], limit: u16) -> Vec<Plan> {
+    thread::scope(|s| {
+        let handles: Vec<_> = bps.iter().map(|bp| s.spawn(move || bp.optimize(limit))).collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
 }
 
 pub fn run(content: &str) {
     let bps: Vec<Blueprint> = content.lines().map(Blueprint::from).collect();
-    let score_1 = bps.iter().map(|x| x.id * x.mine(24)).sum::<u16>();
-    let score_2 = bps[..3].iter().map(|x| x.mine(32)).reduce(|a, b| a * b).unwrap();
+    let plans_1 = optimize_all(&bps, 24);
+    let score_1 = bps.iter().zip(&plans_1).map(|(x, p)| x.id * p.geodes).sum::<u16>();
+    let plans_2 = optimize_all(&bps[..3.min(bps.len())], 32);
+    let score_2 = plans_2.iter().map(|p| p.geodes).product::<u16>();
     println!("{} {}", score_1, score_2);
+    if env::args().any(|a| a == "--trace") {
+        for (bp, plan) in bps.iter().zip(&plans_1) {
+            println!("Blueprint {}: {} geodes", bp.id, plan.geodes);
+            for line in bp.timeline(24, plan) { println!("  {}", line); }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -106,4 +182,30 @@ mod tests {
             Each geode robot costs 2 ore and 7 obsidian.");
         assert_eq!(bp.mine(24), 9);
     }
+
+    #[test]
+    pub fn schedule() {
+        let bps: Vec<super::Blueprint> = [
+            "Blueprint 1: \
+            Each ore robot costs 4 ore. \
+            Each clay robot costs 2 ore. \
+            Each obsidian robot costs 3 ore and 14 clay. \
+            Each geode robot costs 2 ore and 7 obsidian.",
+            "Blueprint 2: \
+            Each ore robot costs 2 ore. \
+            Each clay robot costs 3 ore. \
+            Each obsidian robot costs 3 ore and 8 clay. \
+            Each geode robot costs 3 ore and 12 obsidian.",
+        ].into_iter().map(super::Blueprint::from).collect();
+        let plans = super::optimize_all(&bps, 24);
+        assert_eq!(plans.iter().map(|p| p.geodes).collect::<Vec<_>>(), [9, 12]);
+        assert_eq!(super::optimize_all(&bps, 32)[0].geodes, 56);
+
+        let lines = bps[0].timeline(24, &plans[0]);
+        assert_eq!(lines.len(), 24);
+        assert_eq!(lines[0], "1: wait, stock 1/0/0/0");
+        assert!(lines[23].ends_with("/9"));
+        let builds = lines.iter().filter(|s| s.contains("build")).count();
+        assert_eq!(builds, plans[0].builds.len());
+    }
 }
@@ -1,14 +1,7 @@
+use crate::expr::{Expr, Op};
+use crate::rational::Rational;
 use std::collections::HashMap;
 
-fn gcd(mut n: u64, mut m: u64) -> u64 {
-    if n == 0 { return m; }
-    while m != 0 {
-        if m < n { (m, n) = (n, m); }
-        m %= n;
-    }
-    n
-}
-
 #[derive(Debug)]
 enum Monkey {
     Num(i64),
@@ -35,6 +28,15 @@ impl Monkey {
     fn id(s: &str) -> u32 {
         u32::from_str_radix(s, 36).unwrap()
     }
+
+    fn name(mut id: u32) -> String {
+        let mut digits = vec![];
+        while id > 0 || digits.is_empty() {
+            digits.push(char::from_digit(id % 36, 36).unwrap());
+            id /= 36;
+        }
+        digits.into_iter().rev().collect()
+    }
 }
 
 #[derive(Debug)]
@@ -42,13 +44,6 @@ struct Banter {
     rules: HashMap<u32, Monkey>,
 }
 
-#[derive(Debug, PartialEq)]
-struct Expr {
-    var: i64,
-    num: i64,
-    den: i64,
-}
-
 impl Banter {
     fn from(input: &str) -> Banter {
         let iter = input.lines().map(|s| {
@@ -60,62 +55,40 @@ impl Banter {
         }
     }
 
-    fn eval(&self, node: u32) -> i64 {
-        match *self.rules.get(&node).unwrap() {
-            Monkey::Num(n) => n,
-            Monkey::Add(a, b) => self.eval(a) + self.eval(b),
-            Monkey::Sub(a, b) => self.eval(a) - self.eval(b),
-            Monkey::Mul(a, b) => self.eval(a) * self.eval(b),
-            Monkey::Div(a, b) => self.eval(a) / self.eval(b),
+    // Expression tree of a monkey, with `var` (if any) left unknown.
+    fn expr(&self, node: u32, var: Option<u32>) -> Result<Expr, String> {
+        if Some(node) == var {
+            return Ok(Expr::Var(Monkey::name(node)));
         }
+        let rule = self.rules.get(&node)
+            .ok_or_else(|| format!("unknown monkey {}", Monkey::name(node)))?;
+        let (op, a, b) = match *rule {
+            Monkey::Num(n) => return Ok(Expr::num(n)),
+            Monkey::Add(a, b) => (Op::Add, a, b),
+            Monkey::Sub(a, b) => (Op::Sub, a, b),
+            Monkey::Mul(a, b) => (Op::Mul, a, b),
+            Monkey::Div(a, b) => (Op::Div, a, b),
+        };
+        Ok(Expr::apply(op, self.expr(a, var)?, self.expr(b, var)?))
     }
 
-    fn eval_var(&self, node: u32, var: u32) -> Expr {
-        if node == var {
-            return Expr { var: 1, num: 0, den: 1 };
-        }
-        let res = match *self.rules.get(&node).unwrap() {
-            Monkey::Num(n) => Expr { var: 0, num: n, den: 1 },
-            Monkey::Add(a, b) => {
-                let [x, y] = [self.eval_var(a, var), self.eval_var(b, var)];
-                Expr { var: x.var * y.den + y.var * x.den,
-                       num: x.num * y.den + y.num * x.den,
-                       den: x.den * y.den }
-            },
-            Monkey::Sub(a, b) => {
-                let [x, y] = [self.eval_var(a, var), self.eval_var(b, var)];
-                Expr { var: x.var * y.den - y.var * x.den,
-                       num: x.num * y.den - y.num * x.den,
-                       den: x.den * y.den }
-            },
-            Monkey::Mul(a, b) => {
-                let [x, y] = [self.eval_var(a, var), self.eval_var(b, var)];
-                assert!(x.var == 0 || y.var == 0);
-                Expr { var: x.var * y.num + y.var * x.num,
-                       num: x.num * y.num,
-                       den: x.den * y.den }
-            },
-            Monkey::Div(a, b) => {
-                let [x, y] = [self.eval_var(a, var), self.eval_var(b, var)];
-                assert!(y.var == 0 && y.num != 0);
-                Expr { var: x.var * y.den,
-                       num: x.num * y.den,
-                       den: x.den * y.num }
-            },
+    fn eval(&self, node: u32) -> Result<Rational, String> {
+        self.expr(node, None)?.eval()
+    }
+
+    // Both sides of `node`'s rule, simplified, with `var` unknown.
+    fn equation(&self, node: u32, var: u32) -> Result<(Expr, Expr), String> {
+        let (a, b) = match *self.rules.get(&node).unwrap() {
+            Monkey::Num(_) => return Err(format!("{} is not an operation", Monkey::name(node))),
+            Monkey::Add(a, b) | Monkey::Sub(a, b) | Monkey::Mul(a, b) | Monkey::Div(a, b) => (a, b),
         };
-        let u = gcd(res.var.abs() as u64, res.num.abs() as u64);
-        let v = gcd(u, res.den.abs() as u64) as i64;
-        Expr { var: res.var / v, num: res.num / v, den: res.den / v }
+        Ok((self.expr(a, Some(var))?.simplify()?, self.expr(b, Some(var))?.simplify()?))
     }
 
-    fn solve(&self, node: u32, var: u32) -> i64 {
-        let root = self.rules.get(&node).unwrap();
-        if let Monkey::Add(a, b) = *root {
-            let [x, y] = [self.eval_var(a, var), self.eval_var(b, var)];
-            (x.num * y.den - y.num * x.den) / (y.var * x.den - x.var * y.den)
-        } else {
-            panic!();
-        }
+    // Value of `var` that makes both sides of `node` equal.
+    fn solve(&self, node: u32, var: u32) -> Result<Rational, String> {
+        let (a, b) = self.equation(node, var)?;
+        a.solve(&b, &Monkey::name(var))
     }
 }
 
@@ -123,11 +96,16 @@ pub fn run(content: &str) {
     let inst = Banter::from(content);
     let root = Monkey::id("root");
     let humn = Monkey::id("humn");
-    println!("{} {}", inst.eval(root), inst.solve(root, humn));
+    match (inst.eval(root), inst.solve(root, humn)) {
+        (Ok(value), Ok(solution)) => println!("{} {}", value, solution),
+        (Err(e), _) | (_, Err(e)) => println!("{}", e),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::rational::Rational;
+
     fn example() -> &'static str { r#"
 root: pppw + sjmn
 dbpl: 5
@@ -151,7 +129,48 @@ hmdt: 32"#.trim()
         let inst = super::Banter::from(example());
         let root = super::Monkey::id("root");
         let humn = super::Monkey::id("humn");
-        assert_eq!(inst.eval(root), 152);
-        assert_eq!(inst.solve(root, humn), 301);
+        assert_eq!(inst.eval(root), Ok(Rational::from(152)));
+        assert_eq!(inst.solve(root, humn), Ok(Rational::from(301)));
+    }
+
+    #[test]
+    pub fn infix() {
+        let inst = super::Banter::from(example());
+        let root = super::Monkey::id("root");
+        let humn = super::Monkey::id("humn");
+        let (a, b) = inst.equation(root, humn).unwrap();
+        assert_eq!(a.to_string(), "(4 + 2 * (humn - 3)) / 4");
+        assert_eq!(b.to_string(), "150");
+        assert_eq!(super::Monkey::name(root), "root");
+    }
+
+    #[test]
+    pub fn inverse() {
+        // humn sits on the right of - and /, and is multiplied by 1 on the way.
+        let inst = super::Banter::from("root: aaaa * bbbb\naaaa: cccc / dddd\nbbbb: 3\n\
+                                        cccc: 12\ndddd: eeee - ffff\neeee: 10\nffff: gggg * hhhh\n\
+                                        gggg: humn + zero\nhhhh: 1\nzero: 0\nhumn: 1");
+        let root = super::Monkey::id("root");
+        let humn = super::Monkey::id("humn");
+        let (a, b) = inst.equation(root, humn).unwrap();
+        assert_eq!(a.to_string(), "12 / (10 - humn)");
+        assert_eq!(b.to_string(), "3");
+        assert_eq!(inst.solve(root, humn), Ok(Rational::from(6)));
+        assert_eq!(inst.eval(super::Monkey::id("aaaa")), Ok(Rational::new(4, 3)));
+    }
+
+    #[test]
+    pub fn errors() {
+        let big = (1..=12).map(|i| format!("m{:03}: m{:03} * m{:03}\n", i, i - 1, i - 1)).collect::<String>();
+        let inst = super::Banter::from(&(big + "m000: 100\nroot: m012 + humn\nhumn: 1"));
+        let root = super::Monkey::id("root");
+        let humn = super::Monkey::id("humn");
+        assert_eq!(inst.eval(root), Err(String::from("arithmetic overflow")));
+
+        let inst = super::Banter::from("root: aaaa + bbbb\naaaa: humn * humn\nbbbb: 4\nhumn: 1");
+        assert_eq!(inst.solve(root, humn), Err(String::from("humn occurs more than once")));
+        let inst = super::Banter::from("root: aaaa + bbbb\naaaa: humn / zero\nbbbb: 4\nzero: 0\nhumn: 1");
+        assert_eq!(inst.eval(root), Err(String::from("division by zero")));
+        assert_eq!(inst.solve(root, humn), Err(String::from("humn is divided by zero")));
     }
 }
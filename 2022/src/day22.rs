@@ -1,5 +1,6 @@
-use std::collections::HashSet;
+use std::env;
 use std::fmt;
+use crate::cube::{Cursor, Net};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum Direction {
@@ -169,192 +170,44 @@ impl<'a> Iterator for MazeIter<'a> {
     }
 }
 
-type Position = (usize, usize);
-struct Surface<'a> {
-    maze: &'a Maze,
-    visited: HashSet<Position>,
-}
-
-impl<'a> Surface<'a> {
-    fn from(maze: &Maze) -> Surface<'_> {
-        Surface { maze, visited: HashSet::new() }
-    }
-
-    fn search(&self, src: Position) -> Option<Vec<Position>> {
-        let mut queue: Vec<Vec<Position>> = vec![vec![src]];
-        let mut visited: HashSet<Position> = HashSet::from([src]);
-        while let Some(path) = queue.pop() {
-            let (x, y) = path[path.len() - 1];
-            for (nx, ny) in [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)] {
-                if self.maze.is_valid(nx, ny) && !visited.contains(&(nx, ny)) {
-                    let mut new_path = path.clone();
-                    new_path.push((nx, ny));
-                    if !self.visited.contains(&(nx, ny)) {
-                        return Some(new_path);
-                    }
-                    queue.push(new_path);
-                    visited.insert((nx, ny));
-                }
-            }
-        }
-        None
-    }
-
-    fn explore(&mut self, mut pos: Position) -> Vec<(Direction, Position)> {
-        let mut result: Vec<(Direction, Position)> = Vec::new();
-        self.visited.insert(pos);
-        while let Some(path) = self.search(pos) {
-            for s in path.windows(2) {
-                let dx = s[1].0 as isize - s[0].0 as isize;
-                let dy = s[1].1 as isize - s[0].1 as isize;
-                let dir = match (dx, dy) {
-                    (0, -1) => Direction::Up,
-                    (0, 1) => Direction::Down,
-                    (-1, 0) => Direction::Left,
-                    (1, 0) => Direction::Right,
-                    _ => panic!()
-                };
-                pos = s[1];
-                result.push((dir, pos));
-                self.visited.insert(pos);
-            }
-        }
-        result
-    }
-
-    fn to_index(&self, (x, y): Position) -> usize {
-        (x - self.maze.rows[y - 1].start) +
-            self.maze.rows[..y - 1].iter().map(|x| x.walls.len()).sum::<usize>()
-    }
-
-    fn to_position(&self, mut index: usize) -> Position {
-        for (y, row) in self.maze.rows.iter().enumerate() {
-            if index < row.walls.len() { return (row.start + index, y + 1); }
-            index -= row.walls.len();
-        }
-        panic!()
-    }
-}
-
 // ----- [3D] ------------------------------------------------------------------
 
-// Sides: (0: front, 1: top, 2: right, 3: bottom, 4: left, 5: back)
-const ROTATIONS: [([u8; 6], Direction); 4] = [
-    ([3, 0, 2, 5, 4, 1], Direction::Up),
-    ([1, 5, 2, 0, 4, 3], Direction::Down),
-    ([2, 1, 5, 3, 0, 4], Direction::Left),
-    ([4, 1, 0, 3, 5, 2], Direction::Right),
-];
-
-fn rotate<T: Copy + fmt::Debug>(cube: [T; 6], dir: Direction) -> [T; 6] {
-    let map_ = ROTATIONS.iter().find(|x| x.1 == dir).unwrap().0;
-    let res = Vec::from_iter(map_.iter().map(|&i| cube[i as usize]));
-    res.try_into().unwrap()
-}
-
-fn reverse(d1: Direction, d2: Direction) -> bool {
-    d1 == d2 ||
-    (d1 == Direction::Right && d2 == Direction::Up) ||
-    (d1 == Direction::Up && d2 == Direction::Right) ||
-    (d1 == Direction::Left && d2 == Direction::Down) ||
-    (d1 == Direction::Down && d2 == Direction::Left)
-}
-
-#[derive(Debug)]
-struct Side {
-    surface: Maze,
-    connect: Vec<(Direction, usize)>,
-    offset: Position,
+impl Direction {
+    fn from_facing(facing: usize) -> Direction {
+        [Direction::Right, Direction::Down, Direction::Left, Direction::Up][facing]
+    }
 }
 
 #[derive(Debug)]
 struct Cube {
-    surface: Maze,
-    sides: [Side; 6],
-    size: usize,
+    net: Net,
 }
 
 impl Cube {
-    fn from(input: &str, size: usize) -> Cube {
-        let mut surface = Maze { rows: Vec::new() };
-        let mut children = Vec::<Maze>::new();
-        let maze = Maze::from(input);
-        assert!(maze.rows.len() % size == 0);
-
-        for (i, row) in maze.rows.into_iter().enumerate() {
-            assert!(row.start % size == 1);
-            assert!(row.end() % size == 0);
-            let n = row.walls.len() / size;
-            if i % size == 0 {
-                surface.rows.push(Row {
-                    walls: vec![1; n],
-                    start: row.start / size + 1,
-                });
-                for _ in 0..n {
-                    children.push(Maze { rows: Vec::new() });
-                }
-            }
-            let p = children.len() - n;
-            for j in 0..n {
-                let m = &mut children[p + j];
-                let a = &row.walls[j * size..(j + 1) * size];
-                m.rows.push(Row {
-                    walls: Vec::from_iter(a.iter().copied()),
-                    start: 1,
-                });
-            }
-        }
-        assert_eq!(children.len(), 6);
-
-        let mut cube = [Some(0), None, None, None, None, None];
-        let mut inst = Surface::from(&surface);
-        for (dir, pos) in inst.explore((surface.rows[0].start, 1)) {
-            cube = rotate(cube, dir.rotate().rotate());
-            let idx = inst.to_index(pos);
-            if let Some(test) = cube[0] {
-                assert_eq!(test, idx);
-            } else {
-                cube[0] = Some(idx);
-            }
-        }
-
-        let mut last = inst.to_position(cube[0].unwrap());
-        let sides: Vec<Side> = (0..6).map(|index| {
-            let (x, y) = inst.to_position(index);
-            inst.visited.clear();
-            for (dir, pos) in inst.explore(last) {
-                cube = rotate(cube, dir.rotate().rotate());
-                last = pos;
-                if pos == (x, y) { break; }
-            }
-            Side {
-                surface: children[index].clone(),
-                connect: vec![
-                    (Direction::Up, cube[1].unwrap()),
-                    (Direction::Down, cube[3].unwrap()),
-                    (Direction::Left, cube[4].unwrap()),
-                    (Direction::Right, cube[2].unwrap()),
-                ],
-                offset: ((x - 1) * size, (y - 1) * size),
-            }
-        }).collect();
-
-        Cube { surface, sides: sides.try_into().unwrap(), size }
+    fn from(input: &str) -> Cube {
+        Cube { net: Net::parse(input).unwrap() }
     }
 
     fn iter(&self, path: &Path) -> CubeIter<'_> {
         CubeIter {
             cube: self,
             path: path.moves.iter().rev().cloned().collect(),
-            pos: (1, 1, 0),
-            dir: Direction::Right,
+            pos: self.net.start(),
         }
     }
+
+    // The unfolded map with the walked path drawn on it.
+    fn trace(&self, path: &Path) -> String {
+        let mut it = self.iter(path);
+        let mut trail: Vec<Cursor> = vec![it.pos];
+        while it.next().is_some() { trail.push(it.pos); }
+        self.net.render(&trail)
+    }
 }
 
 impl fmt::Display for Cube {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.surface)
+        write!(f, "{}", self.net)
     }
 }
 
@@ -362,8 +215,7 @@ impl fmt::Display for Cube {
 struct CubeIter<'a> {
     cube: &'a Cube,
     path: Vec<Move>,
-    pos: (usize, usize, usize),
-    dir: Direction,
+    pos: Cursor,
 }
 
 impl<'a> Iterator for CubeIter<'a> {
@@ -373,43 +225,18 @@ impl<'a> Iterator for CubeIter<'a> {
         self.path.pop().map(|move_| {
             match move_ {
                 Move::Forward(steps) => {
-                    let (mut x, mut y, mut z) = self.pos;
-                    (x, y) = match self.dir {
-                        Direction::Up => (x, y - 1),
-                        Direction::Down => (x, y + 1),
-                        Direction::Left => (x - 1, y),
-                        Direction::Right => (x + 1, y),
-                    };
-                    let (mut d, n) = (self.dir, self.cube.size);
-                    if x == 0 || x > n || y == 0 || y > n {
-                        let mut v = if x % (n + 1) != 0 {x} else {y};
-                        let to = self.cube.sides[z].connect.iter().find(
-                            |&x| x.0 == self.dir).unwrap().1;
-                        let back = self.cube.sides[to].connect.iter().find(
-                            |&x| x.1 == z).unwrap().0;
-                        if reverse(d, back) { v = n - v + 1; }
-
-                        (x, y) = match back {
-                            Direction::Up => (v, 1),
-                            Direction::Down => (v, n),
-                            Direction::Left => (1, v),
-                            Direction::Right => (n, v),
-                        };
-                        (z, d) = (to, back.rotate().rotate());
-                    }
-                    if !self.cube.sides[z].surface.rows[y - 1].has_wall(x) {
-                        self.pos = (x, y, z);
-                        self.dir = d;
+                    if let Some(next) = self.cube.net.forward(&self.pos) {
+                        self.pos = next;
                         if steps > 1 {
                             self.path.push(Move::Forward(steps - 1));
                         }
                     }
                 },
-                Move::Left => self.dir = self.dir.rotate().rotate().rotate(),
-                Move::Right => self.dir = self.dir.rotate(),
+                Move::Left => self.pos = self.pos.turn(false),
+                Move::Right => self.pos = self.pos.turn(true),
             };
-            let offset = self.cube.sides[self.pos.2].offset;
-            (self.pos.0 + offset.0, self.pos.1 + offset.1, self.dir)
+            let (x, y) = self.cube.net.position(&self.pos);
+            (x + 1, y + 1, Direction::from_facing(self.pos.facing))
         })
     }
 }
@@ -430,9 +257,12 @@ where T: Iterator<Item = (usize, usize, Direction)> {
 pub fn run(content: &str) {
     let parts: Vec<&str> = content.trim_end().split("\n\n").collect();
     let maze = Maze::from(parts[0]);
-    let cube = Cube::from(parts[0], 50);
+    let cube = Cube::from(parts[0]);
     let path = Path::from(parts[1]);
     println!("{} {}", score(maze.iter(&path)), score(cube.iter(&path)));
+    if env::args().any(|a| a == "--trace") {
+        print!("{}", cube.trace(&path));
+    }
 }
 
 #[cfg(test)]
@@ -459,7 +289,95 @@ mod tests {
         let path = super::Path::from("10R5L5R10L4R5L5");
         assert_eq!(super::score(maze.iter(&path)), 6032);
 
-        let cube = super::Cube::from(example(), 4);
+        let cube = super::Cube::from(example());
         assert_eq!(super::score(cube.iter(&path)), 5031);
     }
+
+    #[test]
+    pub fn stitching() {
+        let cube = super::Cube::from(example());
+        assert_eq!(cube.net.size, 4);
+        let table = cube.to_string();
+        assert!(table.starts_with("0 right -> 5 right (2 turns)\n"));
+        assert!(table.contains("3 right -> 5 up (1 turns)\n"));
+
+        let path = super::Path::from("10R5L5R10L4R5L5");
+        let trace = cube.trace(&path);
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines[0], "        >>v#");
+        assert_eq!(lines[4], "...#..^...v#");
+        assert_eq!(lines[10], "        .#v<<<<.");
+    }
+
+    // Blocks of a hexomino as a map with faces of the given size.
+    fn draw(cells: &[(usize, usize)], size: usize) -> String {
+        let h = cells.iter().map(|c| c.1).max().unwrap() + 1;
+        (0..h * size).map(|y| {
+            let w = cells.iter().filter(|c| c.1 == y / size).map(|c| c.0).max().unwrap() + 1;
+            (0..w * size).map(|x| {
+                if cells.contains(&(x / size, y / size)) {'.'} else {' '}
+            }).collect::<String>() + "\n"
+        }).collect()
+    }
+
+    #[test]
+    pub fn nets() {
+        use std::collections::BTreeSet;
+        use super::{Cursor, Net};
+        type Shape = Vec<(usize, usize)>;
+
+        let normalize = |cells: Vec<(i32, i32)>| -> Shape {
+            let (mx, my) = (cells.iter().map(|c| c.0).min().unwrap(),
+                            cells.iter().map(|c| c.1).min().unwrap());
+            let mut a: Shape = cells.iter().map(|c| ((c.0 - mx) as usize, (c.1 - my) as usize)).collect();
+            a.sort();
+            a
+        };
+        // Every fixed hexomino, grown cell by cell.
+        let mut shapes = BTreeSet::from([vec![(0, 0)]]);
+        for _ in 1..6 {
+            shapes = shapes.iter().flat_map(|s| {
+                s.iter().flat_map(|&(x, y)| {
+                    let (x, y) = (x as i32, y as i32);
+                    [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)].map(|c| {
+                        let mut cells: Vec<(i32, i32)> = s.iter().map(|&(a, b)| (a as i32, b as i32)).collect();
+                        if !cells.contains(&c) { cells.push(c); }
+                        cells
+                    })
+                }).filter(|c| c.len() == s.len() + 1).map(normalize).collect::<Vec<_>>()
+            }).collect();
+        }
+        assert_eq!(shapes.len(), 216);
+
+        let folding: Vec<&Shape> = shapes.iter().filter(|s| Net::parse(&draw(s, 2)).is_ok()).collect();
+        // Eleven free nets, each in all eight orientations except the one
+        // with a symmetry (four orientations).
+        let free: BTreeSet<Shape> = folding.iter().map(|s| {
+            (0..8).map(|k| normalize(s.iter().map(|&(x, y)| {
+                let (x, y) = (x as i32, y as i32);
+                let (x, y) = if k & 4 != 0 {(y, x)} else {(x, y)};
+                match k % 4 { 0 => (x, y), 1 => (-y, x), 2 => (-x, -y), _ => (y, -x) }
+            }).collect())).min().unwrap()
+        }).collect();
+        assert_eq!(free.len(), 11);
+
+        for s in folding {
+            let net = Net::parse(&draw(s, 3)).unwrap();
+            for (face, side, glue) in net.stitching() {
+                let back = net.glue(glue.face, glue.side);
+                assert_eq!((back.face, back.side, (back.turns + glue.turns) % 4), (face, side, 0));
+            }
+            // Walking straight ahead goes around the cube.
+            for face in 0..6 {
+                for facing in 0..4 {
+                    let start = Cursor { face, x: 1, y: 0, facing };
+                    let mut c = start;
+                    for _ in 0..12 { c = net.forward(&c).unwrap(); }
+                    assert_eq!(c, start);
+                }
+            }
+        }
+        assert!(Net::parse("......").is_err());
+        assert!(Net::parse(&draw(&[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)], 1)).is_err());
+    }
 }
@@ -0,0 +1,159 @@
+use crate::rational::Rational;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Num(Rational),
+    Var(String),
+    Apply(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Op {
+    pub fn apply(self, a: Rational, b: Rational) -> Result<Rational, String> {
+        match self {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div => a.checked_div(b),
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+}
+
+impl Expr {
+    pub fn num(value: i64) -> Expr {
+        Expr::Num(Rational::from(value as i128))
+    }
+
+    pub fn apply(op: Op, a: Expr, b: Expr) -> Expr {
+        Expr::Apply(op, Box::new(a), Box::new(b))
+    }
+
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Var(v) => v == var,
+            Expr::Apply(_, a, b) => a.contains(var) || b.contains(var),
+        }
+    }
+
+    pub fn eval(&self) -> Result<Rational, String> {
+        match self {
+            Expr::Num(n) => Ok(*n),
+            Expr::Var(v) => Err(format!("unbound variable {}", v)),
+            Expr::Apply(op, a, b) => op.apply(a.eval()?, b.eval()?),
+        }
+    }
+
+    // Folds constant subtrees and drops neutral operands.
+    pub fn simplify(self) -> Result<Expr, String> {
+        let Expr::Apply(op, a, b) = self else { return Ok(self) };
+        let (a, b) = (a.simplify()?, b.simplify()?);
+        let value = |e: &Expr| if let Expr::Num(n) = e {Some(*n)} else {None};
+        let (one, zero) = (Rational::from(1), Rational::from(0));
+        Ok(match (op, value(&a), value(&b)) {
+            (_, Some(x), Some(y)) => Expr::Num(op.apply(x, y)?),
+            (Op::Add, Some(x), _) if x.is_zero() => b,
+            (Op::Add | Op::Sub, _, Some(y)) if y.is_zero() => a,
+            (Op::Mul, Some(x), _) if x == one => b,
+            (Op::Mul | Op::Div, _, Some(y)) if y == one => a,
+            (Op::Mul, Some(x), _) | (Op::Mul, _, Some(x)) if x.is_zero() => Expr::Num(zero),
+            _ => Expr::apply(op, a, b),
+        })
+    }
+
+    // Value of `var` that makes `self` equal to `other`, found by undoing
+    // the operations on the path from the root down to the variable. The
+    // variable must occur exactly once.
+    pub fn solve(&self, other: &Expr, var: &str) -> Result<Rational, String> {
+        let (mut side, mut target) = match (self.contains(var), other.contains(var)) {
+            (true, false) => (self, other.eval()?),
+            (false, true) => (other, self.eval()?),
+            (true, true) => return Err(format!("{} occurs on both sides", var)),
+            (false, false) => return Err(format!("{} does not occur", var)),
+        };
+        loop {
+            match side {
+                Expr::Var(_) => return Ok(target),
+                Expr::Num(_) => unreachable!(),
+                Expr::Apply(op, a, b) => {
+                    let (a_var, b_var) = (a.contains(var), b.contains(var));
+                    if a_var && b_var {
+                        return Err(format!("{} occurs more than once", var));
+                    }
+                    if a_var {
+                        let v = b.eval()?;
+                        target = match op {
+                            Op::Add => target.checked_sub(v)?,
+                            Op::Sub => target.checked_add(v)?,
+                            Op::Mul if v.is_zero() => return Err(format!("{} is multiplied by zero", var)),
+                            Op::Mul => target.checked_div(v)?,
+                            Op::Div if v.is_zero() => return Err(format!("{} is divided by zero", var)),
+                            Op::Div => target.checked_mul(v)?,
+                        };
+                        side = a;
+                    } else {
+                        let v = a.eval()?;
+                        target = match op {
+                            Op::Add => target.checked_sub(v)?,
+                            Op::Sub => v.checked_sub(target)?,
+                            Op::Mul if v.is_zero() => return Err(format!("{} is multiplied by zero", var)),
+                            Op::Mul => target.checked_div(v)?,
+                            Op::Div if target.is_zero() => return Err(format!("no value of {} divides to zero", var)),
+                            Op::Div => v.checked_div(target)?,
+                        };
+                        side = b;
+                    }
+                }
+            }
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, parent: u8) -> fmt::Result {
+        match self {
+            Expr::Num(n) if !n.is_integer() || n.signum() < 0 => write!(f, "({})", n),
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Var(v) => write!(f, "{}", v),
+            Expr::Apply(op, a, b) => {
+                let p = op.precedence();
+                if p < parent { write!(f, "(")?; }
+                a.write(f, p)?;
+                write!(f, " {} ", op.symbol())?;
+                // The right operand of - and / groups even at equal precedence.
+                let right = if matches!(op, Op::Sub | Op::Div) {p + 1} else {p};
+                b.write(f, right)?;
+                if p < parent { write!(f, ")")?; }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
@@ -26,16 +26,23 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+mod cube;
+mod expr;
+mod vfs;
+#[path = "../../common/ocr.rs"]
+mod ocr;
+#[path = "../../common/rational.rs"]
+mod rational;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     assert!(args.len() > 1, "Task number is missing");
 
     let number: usize = args[1].parse().unwrap();
-    let filename: String = if args.len() > 2 {
-        args[2].clone()
-    } else {
-        format!("input/day{:02}.txt", number)
+    // Arguments starting with "--" are options for the day itself.
+    let filename: String = match args[2..].iter().find(|a| !a.starts_with("--")) {
+        Some(name) => name.clone(),
+        None => format!("input/day{:02}.txt", number),
     };
     let content = fs::read_to_string(filename).expect("Error reading input");
 
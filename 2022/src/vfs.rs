@@ -0,0 +1,298 @@
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    // Children by name, so listings and renderings come out sorted.
+    Directory(BTreeMap<String, usize>),
+    File(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub kind: Kind,
+    listed: bool,
+}
+
+// Something in the transcript that does not agree with what was seen
+// before; `line` counts from one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issue {
+    pub line: usize,
+    pub message: String,
+}
+
+// Tree rebuilt from a shell transcript. Nodes live in an arena, the root
+// is node 0; directory sizes are computed on first use and recomputed
+// after the tree changes.
+#[derive(Debug)]
+pub struct FileSystem {
+    nodes: Vec<Node>,
+    sizes: OnceCell<Vec<usize>>,
+}
+
+pub const ROOT: usize = 0;
+
+impl Node {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, Kind::Directory(_))
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out + "\""
+}
+
+impl FileSystem {
+    #[allow(dead_code)]
+    pub fn from(input: &str) -> FileSystem {
+        FileSystem::replay(input).0
+    }
+
+    // Replays `cd` and `ls` commands. Inconsistent output (a directory
+    // listed twice with different contents, an entry that changes type or
+    // size, a `cd` into something never listed) is reported; the later
+    // output wins, so entries missing from a new listing are dropped, and
+    // replay goes on. Issues come in line order; a missing entry is
+    // reported at the `ls` that left it out.
+    pub fn replay(input: &str) -> (FileSystem, Vec<Issue>) {
+        let mut fs = FileSystem {
+            nodes: vec![Node {
+                name: String::from("/"),
+                parent: None,
+                kind: Kind::Directory(BTreeMap::new()),
+                listed: false,
+            }],
+            sizes: OnceCell::new(),
+        };
+        let mut issues = vec![];
+        let mut current = ROOT;
+        // Line of the `ls` being read and the names seen in its output so far.
+        let mut listing: Option<(usize, Vec<String>)> = None;
+
+        for (index, line) in input.lines().enumerate() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.first() == Some(&"$") {
+                if let Some((line, seen)) = listing.take() {
+                    fs.finish_listing(current, seen, &mut |message| issues.push(Issue { line, message }));
+                }
+            }
+            let mut issue = |message: String| issues.push(Issue { line: index + 1, message });
+            match tokens[..] {
+                ["$", "cd", "/"] => current = ROOT,
+                ["$", "cd", ".."] => match fs.nodes[current].parent {
+                    Some(parent) => current = parent,
+                    None => issue(String::from("cd .. at the root")),
+                },
+                ["$", "cd", name] => {
+                    current = match fs.child(current, name) {
+                        Some(id) if fs.nodes[id].is_dir() => id,
+                        Some(id) => {
+                            issue(format!("cd into file {}", fs.path(id)));
+                            fs.insert(current, name, Kind::Directory(BTreeMap::new()))
+                        },
+                        None => {
+                            let id = fs.insert(current, name, Kind::Directory(BTreeMap::new()));
+                            issue(format!("cd into unlisted directory {}", fs.path(id)));
+                            id
+                        },
+                    }
+                },
+                ["$", "ls"] => listing = Some((index + 1, vec![])),
+                [size_or_dir, name] if !line.starts_with('$') => {
+                    let Some((_, seen)) = listing.as_mut() else {
+                        issue(format!("output outside of ls: {}", line));
+                        continue;
+                    };
+                    let kind = match size_or_dir {
+                        "dir" => Kind::Directory(BTreeMap::new()),
+                        size => match size.parse() {
+                            Ok(size) => Kind::File(size),
+                            Err(_) => {
+                                issue(format!("bad size {}", size));
+                                continue;
+                            },
+                        },
+                    };
+                    seen.push(String::from(name));
+                    match fs.child(current, name) {
+                        Some(id) => match (&fs.nodes[id].kind, &kind) {
+                            (Kind::Directory(_), Kind::Directory(_)) => (),
+                            (Kind::File(a), Kind::File(b)) if a == b => (),
+                            (old, _) => {
+                                issue(format!("{} was {}", fs.path(id), match old {
+                                    Kind::Directory(_) => String::from("a directory"),
+                                    Kind::File(size) => format!("a file of size {}", size),
+                                }));
+                                fs.insert(current, name, kind);
+                            },
+                        },
+                        None if fs.nodes[current].listed => {
+                            let id = fs.insert(current, name, kind);
+                            issue(format!("{} was not listed before", fs.path(id)));
+                        },
+                        None => { fs.insert(current, name, kind); },
+                    }
+                },
+                _ => issue(format!("unrecognized line: {}", line)),
+            }
+        }
+        if let Some((line, seen)) = listing {
+            fs.finish_listing(current, seen, &mut |message| issues.push(Issue { line, message }));
+        }
+        issues.sort_by_key(|issue| issue.line);
+        (fs, issues)
+    }
+
+    // Drops the entries of an earlier listing that are missing from this one.
+    fn finish_listing<F: FnMut(String)>(&mut self, dir: usize, seen: Vec<String>, issue: &mut F) {
+        if self.nodes[dir].listed {
+            let Kind::Directory(children) = &self.nodes[dir].kind else { unreachable!() };
+            let gone = children.iter().filter(|(name, _)| !seen.contains(name))
+                .map(|(name, &id)| (name.clone(), id)).collect::<Vec<_>>();
+            for (name, id) in gone {
+                issue(format!("{} disappeared from the listing", self.path(id)));
+                if let Kind::Directory(children) = &mut self.nodes[dir].kind {
+                    children.remove(&name);
+                }
+            }
+            self.sizes = OnceCell::new();
+        }
+        self.nodes[dir].listed = true;
+    }
+
+    // Adds or replaces the named child of a directory; a replaced entry
+    // and everything below it becomes unreachable.
+    fn insert(&mut self, dir: usize, name: &str, kind: Kind) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node { name: String::from(name), parent: Some(dir), kind, listed: false });
+        if let Kind::Directory(children) = &mut self.nodes[dir].kind {
+            children.insert(String::from(name), id);
+        }
+        self.sizes = OnceCell::new();
+        id
+    }
+
+    pub fn child(&self, dir: usize, name: &str) -> Option<usize> {
+        match &self.nodes[dir].kind {
+            Kind::Directory(children) => children.get(name).copied(),
+            Kind::File(_) => None,
+        }
+    }
+
+    pub fn children(&self, dir: usize) -> impl Iterator<Item = usize> + '_ {
+        let children = match &self.nodes[dir].kind {
+            Kind::Directory(children) => Some(children.values().copied()),
+            Kind::File(_) => None,
+        };
+        children.into_iter().flatten()
+    }
+
+    // Node reached by a slash-separated path from the root.
+    #[allow(dead_code)]
+    pub fn lookup(&self, path: &str) -> Option<usize> {
+        path.split('/').filter(|s| !s.is_empty())
+            .try_fold(ROOT, |dir, name| self.child(dir, name))
+    }
+
+    pub fn path(&self, id: usize) -> String {
+        let mut names = vec![];
+        let mut cur = id;
+        while let Some(parent) = self.nodes[cur].parent {
+            names.push(self.nodes[cur].name.as_str());
+            cur = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Reachable nodes below `dir`, parents before children.
+    pub fn walk(&self, dir: usize) -> Vec<usize> {
+        let mut result = vec![];
+        let mut stack = vec![dir];
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            let mut children = self.children(id).collect::<Vec<_>>();
+            children.reverse();
+            stack.extend(children);
+        }
+        result
+    }
+
+    // Total size of every node, filled bottom-up on first use.
+    pub fn size(&self, id: usize) -> usize {
+        self.sizes.get_or_init(|| {
+            let mut sizes = vec![0; self.nodes.len()];
+            for id in self.walk(ROOT).into_iter().rev() {
+                sizes[id] = match &self.nodes[id].kind {
+                    Kind::File(size) => *size,
+                    Kind::Directory(children) => children.values().map(|&c| sizes[c]).sum(),
+                };
+            }
+            sizes
+        })[id]
+    }
+
+    // Directories below `dir` with their sizes, like `du`: children first.
+    pub fn du(&self, dir: usize) -> Vec<(String, usize)> {
+        self.walk(dir).into_iter().rev()
+            .filter(|&id| self.nodes[id].is_dir())
+            .map(|id| (self.path(id), self.size(id)))
+            .collect()
+    }
+
+    // Nodes below `dir` accepted by `pred`, given the node and its size.
+    pub fn find<F: Fn(&Node, usize) -> bool>(&self, dir: usize, pred: F) -> Vec<usize> {
+        self.walk(dir).into_iter().filter(|&id| pred(&self.nodes[id], self.size(id))).collect()
+    }
+
+    // Indented listing in the format of the puzzle statement.
+    pub fn tree(&self, dir: usize) -> String {
+        let mut out = String::new();
+        let mut stack = vec![(dir, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            let node = &self.nodes[id];
+            let what = match node.kind {
+                Kind::Directory(_) => String::from("dir"),
+                Kind::File(size) => format!("file, size={}", size),
+            };
+            out += &format!("{}- {} ({})\n", "  ".repeat(depth), node.name, what);
+            let mut children = self.children(id).map(|c| (c, depth + 1)).collect::<Vec<_>>();
+            children.reverse();
+            stack.extend(children);
+        }
+        out
+    }
+
+    pub fn to_json(&self, id: usize) -> String {
+        let node = &self.nodes[id];
+        let name = json_string(&node.name);
+        match node.kind {
+            Kind::File(size) => format!("{{\"name\":{},\"type\":\"file\",\"size\":{}}}", name, size),
+            Kind::Directory(_) => {
+                let children = self.children(id).map(|c| self.to_json(c)).collect::<Vec<_>>();
+                format!("{{\"name\":{},\"type\":\"dir\",\"size\":{},\"children\":[{}]}}",
+                        name, self.size(id), children.join(","))
+            },
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+pub const START: &str = "broadcaster";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pulse {
+  Low,
+  High,
+}
+
+#[derive(Clone, Debug)]
+enum Module {
+  Broadcaster,
+  FlipFlop(Pulse),
+  Conjunction(HashMap<String, Pulse>),
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+  name: String,
+  module: Module,
+  target: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Relay {
+  nodes: HashMap<String, Node>,
+}
+
+// Part of the circuit that drives one input of the final conjunction.
+#[derive(Clone, Debug)]
+pub struct Counter {
+  pub output: String,
+  pub nodes: Vec<String>,
+  // Presses before the state starts repeating, and the repeat length.
+  pub phase: usize,
+  pub period: usize,
+  // Presses (from 1 to phase + period) that send a high pulse to the
+  // final conjunction.
+  pub hits: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Analysis {
+  pub target: String,
+  pub collector: String,
+  pub counters: Vec<Counter>,
+}
+
+impl Pulse {
+  fn inverse(&self) -> Pulse {
+    match self {
+      Pulse::Low => Pulse::High,
+      Pulse::High => Pulse::Low,
+    }
+  }
+}
+
+impl Module {
+  fn process(&mut self, signal: Pulse, from: &str) -> Option<Pulse> {
+    match self {
+      Module::Broadcaster => Some(signal),
+      Module::FlipFlop(state) => {
+        match signal {
+          Pulse::High => None,
+          Pulse::Low => {
+            *state = state.inverse();
+            Some(*state)
+          },
+        }
+      },
+      Module::Conjunction(cmap) => {
+        *cmap.get_mut(from).unwrap() = signal;
+        let all_high = cmap.values().all(|v| matches!(v, Pulse::High));
+        Some(if all_high {Pulse::Low} else {Pulse::High})
+      },
+    }
+  }
+}
+
+impl Node {
+  fn parse(text: &str) -> Node {
+    let (s1, s2) = text.split_once(" -> ").unwrap();
+    let target = s2.split(", ").map(String::from).collect::<Vec<_>>();
+    let (name, module) = match s1.chars().nth(0).unwrap() {
+      '%' => (&s1[1..], Module::FlipFlop(Pulse::Low)),
+      '&' => (&s1[1..], Module::Conjunction(HashMap::new())),
+      _ => (s1, Module::Broadcaster),
+    };
+    Node { name: String::from(name), module, target }
+  }
+}
+
+impl Counter {
+  fn is_hit(&self, press: usize) -> bool {
+    let press = if press > self.phase {
+      (press - self.phase - 1) % self.period + self.phase + 1
+    } else { press };
+    self.hits.contains(&press)
+  }
+}
+
+impl Analysis {
+  // First press on which every counter sends a high pulse to the collector.
+  pub fn first_press(&self) -> Option<usize> {
+    let phase = self.counters.iter().map(|c| c.phase).max().unwrap_or(0);
+    if let Some(n) = (1..=phase).find(|&n| self.counters.iter().all(|c| c.is_hit(n))) {
+      return Some(n);
+    }
+    // Past every phase each counter is periodic, so combine the residues.
+    let mut options = vec![(0_i128, 1_i128)];
+    for c in &self.counters {
+      let period = c.period as i128;
+      let residues = c.hits.iter().filter(|&&h| h > c.phase)
+        .map(|&h| h as i128 % period).collect::<BTreeSet<_>>();
+      options = options.iter().flat_map(|&(r1, m1)| {
+        residues.iter().filter_map(move |&r2| crt(r1, m1, r2, period))
+      }).collect();
+    }
+    let low = phase as i128 + 1;
+    options.into_iter().map(|(r, m)| (low + (r - low).rem_euclid(m)) as usize).min()
+  }
+}
+
+// One line per counter: what it drives and how it repeats.
+impl fmt::Display for Analysis {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{} <- {}", self.target, self.collector)?;
+    for c in &self.counters {
+      writeln!(f, "  {} ({} nodes): phase {}, period {}, hits {:?}",
+               c.output, c.nodes.len(), c.phase, c.period, c.hits)?;
+    }
+    Ok(())
+  }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+  if b == 0 {a} else {gcd(b, a % b)}
+}
+
+fn inverse(a: i128, m: i128) -> i128 {
+  let (mut t, mut nt, mut r, mut nr) = (0_i128, 1_i128, m, a.rem_euclid(m));
+  while nr != 0 {
+    let q = r / nr;
+    (t, nt) = (nt, t - q * nt);
+    (r, nr) = (nr, r - q * nr);
+  }
+  t.rem_euclid(m)
+}
+
+// Combines x = r1 (mod m1) and x = r2 (mod m2), if consistent.
+fn crt(r1: i128, m1: i128, r2: i128, m2: i128) -> Option<(i128, i128)> {
+  let g = gcd(m1, m2);
+  if (r2 - r1) % g != 0 { return None; }
+  let m = m2 / g;
+  let k = ((r2 - r1) / g).rem_euclid(m) * inverse(m1 / g, m) % m;
+  let lcm = m1 * m;
+  Some(((r1 + m1 * k).rem_euclid(lcm), lcm))
+}
+
+impl Relay {
+  pub fn parse(text: &str) -> Relay {
+    let mut nodes = text.lines().map(Node::parse)
+      .map(|x| (x.name.clone(), x)).collect::<HashMap<_,_>>();
+    let mut rmap = HashMap::<String, Vec<String>>::new();
+    for node in nodes.values() {
+      for t in &node.target {
+        rmap.entry(t.clone()).or_default().push(node.name.clone());
+      }
+    }
+    for (k, v) in rmap.into_iter() {
+      if let Some(x) = nodes.get_mut(&k) {
+        if let Module::Conjunction(cmap) = &mut x.module {
+          cmap.extend(v.into_iter().map(|s| (s, Pulse::Low)));
+        }
+      }
+    }
+    assert!(nodes.contains_key(START));
+    Relay { nodes }
+  }
+
+  // Sends a low pulse to `start`; `observe` sees every pulse (from, to, signal).
+  pub fn press_with<F>(&mut self, start: &str, mut observe: F)
+  where F: FnMut(&str, &str, Pulse) {
+    let init = (String::new(), String::from(start), Pulse::Low);
+    let mut queue = VecDeque::from([init]);
+    while let Some((from, to, signal)) = queue.pop_front() {
+      observe(&from, &to, signal);
+      if let Some(node) = self.nodes.get_mut(&to) {
+        if let Some(next) = node.module.process(signal, &from) {
+          queue.extend(node.target.iter()
+            .map(|v| (to.clone(), v.clone(), next)));
+        }
+      }
+    }
+  }
+
+  pub fn press(&mut self, start: &str) -> (usize, usize) {
+    let (mut low, mut high) = (0_usize, 0_usize);
+    self.press_with(start, |_, _, signal| match signal {
+      Pulse::Low => low += 1,
+      Pulse::High => high += 1,
+    });
+    (low, high)
+  }
+
+  pub fn repeat(&mut self, count: usize) -> usize {
+    let (low, high) = (0..count).fold((0_usize, 0_usize), |acc, _| {
+      let cur = self.press(START);
+      (acc.0 + cur.0, acc.1 + cur.1)
+    });
+    low * high
+  }
+
+  fn state(&self) -> Vec<bool> {
+    let mut names = self.nodes.keys().collect::<Vec<_>>();
+    names.sort();
+    names.into_iter().flat_map(|name| match &self.nodes[name].module {
+      Module::Broadcaster => vec![],
+      Module::FlipFlop(v) => vec![*v == Pulse::High],
+      Module::Conjunction(cmap) => {
+        let mut inputs = cmap.iter().collect::<Vec<_>>();
+        inputs.sort_by_key(|(k, _)| *k);
+        inputs.into_iter().map(|(_, v)| *v == Pulse::High).collect()
+      },
+    }).collect()
+  }
+
+  fn sources(&self, name: &str) -> Vec<String> {
+    let mut result = self.nodes.values()
+      .filter(|n| n.target.iter().any(|t| t == name))
+      .map(|n| n.name.clone()).collect::<Vec<_>>();
+    result.sort();
+    result
+  }
+
+  // Every node that can influence `name`, except the broadcaster.
+  fn ancestors(&self, name: &str) -> BTreeSet<String> {
+    let mut result = BTreeSet::from([name.to_string()]);
+    let mut queue = vec![name.to_string()];
+    while let Some(cur) = queue.pop() {
+      for s in self.sources(&cur) {
+        if s != START && result.insert(s.clone()) { queue.push(s); }
+      }
+    }
+    result
+  }
+
+  fn measure(&self, output: &str, collector: &str, nodes: &BTreeSet<String>,
+             limit: usize) -> Result<Counter, String> {
+    let mut part = self.nodes.iter()
+      .filter(|(k, _)| nodes.contains(*k))
+      .map(|(k, v)| (k.clone(), v.clone())).collect::<HashMap<_,_>>();
+    let mut root = self.nodes[START].clone();
+    root.target.retain(|t| nodes.contains(t));
+    if root.target.is_empty() {
+      return Err(format!("{} is not driven by {}", output, START));
+    }
+    part.insert(START.to_string(), root);
+    let mut sub = Relay { nodes: part };
+
+    let mut seen = HashMap::from([(sub.state(), 0_usize)]);
+    let mut hits = vec![];
+    for press in 1..=limit {
+      let mut hit = false;
+      sub.press_with(START, |from, to, signal| {
+        hit |= from == output && to == collector && signal == Pulse::High;
+      });
+      if hit { hits.push(press); }
+      if let Some(&phase) = seen.get(&sub.state()) {
+        return Ok(Counter {
+          output: output.to_string(),
+          nodes: nodes.iter().cloned().collect(),
+          phase, period: press - phase, hits,
+        });
+      }
+      seen.insert(sub.state(), press);
+    }
+    Err(format!("{} does not repeat within {} presses", output, limit))
+  }
+
+  // Splits the circuit feeding `target` into independent counters, checking
+  // that it has the expected shape: a single conjunction drives `target`,
+  // and the parts behind each of its inputs share no modules.
+  pub fn analyze(&self, target: &str) -> Result<Analysis, String> {
+    let feeders = self.sources(target);
+    let collector = match feeders.as_slice() {
+      [one] => one.clone(),
+      _ => return Err(format!("{} has {} inputs, expected one", target, feeders.len())),
+    };
+    if !matches!(self.nodes[&collector].module, Module::Conjunction(_)) {
+      return Err(format!("{} is not a conjunction", collector));
+    }
+    let mut used = HashSet::<String>::new();
+    let mut counters = vec![];
+    for output in self.sources(&collector) {
+      let nodes = self.ancestors(&output);
+      if nodes.contains(&collector) {
+        return Err(format!("{} depends on {}", output, collector));
+      }
+      if let Some(shared) = nodes.iter().find(|n| used.contains(*n)) {
+        return Err(format!("{} is shared between counters", shared));
+      }
+      used.extend(nodes.iter().cloned());
+      counters.push(self.measure(&output, &collector, &nodes, 1 << 20)?);
+    }
+    Ok(Analysis { target: target.to_string(), collector, counters })
+  }
+
+  #[allow(dead_code)]
+  pub fn to_dot(&self) -> String {
+    let mut names = self.nodes.keys().collect::<Vec<_>>();
+    names.sort();
+    let mut lines = vec![String::from("digraph circuit {")];
+    for name in &names {
+      let shape = match self.nodes[*name].module {
+        Module::Broadcaster => "box",
+        Module::FlipFlop(_) => "diamond",
+        Module::Conjunction(_) => "ellipse",
+      };
+      lines.push(format!("  {} [shape={}];", name, shape));
+    }
+    for name in &names {
+      for t in &self.nodes[*name].target {
+        lines.push(format!("  {} -> {};", name, t));
+      }
+    }
+    lines.push(String::from("}"));
+    lines.join("\n")
+  }
+}
@@ -0,0 +1,185 @@
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+// Undirected multigraph with named vertices and unit edge weights; a pair
+// connected twice has two parallel edges.
+#[derive(Debug)]
+pub struct Graph {
+  pub names: Vec<String>,
+  pub conn: Vec<Vec<usize>>,
+}
+
+// Edges separating the vertices on one side from the rest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cut {
+  pub edges: Vec<(usize, usize)>,
+  pub side: Vec<bool>,
+}
+
+impl Cut {
+  fn new(graph: &Graph, side: Vec<bool>) -> Cut {
+    let edges = graph.conn.iter().enumerate().flat_map(|(i, a)| {
+      a.iter().filter(move |&&j| i < j).map(move |&j| (i, j))
+    }).filter(|&(i, j)| side[i] != side[j]).collect();
+    Cut { edges, side }
+  }
+
+  pub fn sizes(&self) -> (usize, usize) {
+    let inside = self.side.iter().filter(|&&s| s).count();
+    (inside, self.side.len() - inside)
+  }
+}
+
+impl Graph {
+  pub fn parse(text: &str) -> Graph {
+    let mut names: Vec<String> = vec![];
+    let mut conn: Vec<Vec<usize>> = vec![];
+    let mut rmap = HashMap::<String, usize>::new();
+    let mut index = |s: &str| -> usize {
+      *rmap.entry(String::from(s)).or_insert_with(|| {
+        names.push(String::from(s));
+        names.len() - 1
+      })
+    };
+    for line in text.lines() {
+      let (s1, s2) = line.split_once(": ").unwrap();
+      let i = index(s1);
+      if i == conn.len() { conn.push(vec![]); }
+      for to in s2.split(' ') {
+        let j = index(to);
+        if j == conn.len() { conn.push(vec![]); }
+        conn[i].push(j);
+        conn[j].push(i);
+      }
+    }
+    Graph { names, conn }
+  }
+
+  // Edmonds-Karp with a capacity of one per edge, so parallel edges add
+  // up: augments along shortest paths until `limit` edge-disjoint paths are
+  // found or none is left. Returns the flow and the vertices still reachable
+  // from `from` in the residual graph.
+  fn max_flow(&self, from: usize, to: usize, limit: usize) -> (usize, Vec<bool>) {
+    if from == to {
+      // Nothing separates a vertex from itself.
+      return (0, (0..self.names.len()).map(|i| i == from).collect());
+    }
+    let mut residual = HashMap::<(usize, usize), i32>::new();
+    for (i, a) in self.conn.iter().enumerate() {
+      for &j in a { *residual.entry((i, j)).or_default() += 1; }
+    }
+    let mut total = 0;
+    loop {
+      let mut prev = vec![None; self.names.len()];
+      prev[from] = Some(from);
+      let mut queue = VecDeque::from([from]);
+      while let Some(cur) = queue.pop_front() {
+        if cur == to { break; }
+        for &next in &self.conn[cur] {
+          if prev[next].is_none() && residual[&(cur, next)] > 0 {
+            prev[next] = Some(cur);
+            queue.push_back(next);
+          }
+        }
+      }
+      if prev[to].is_none() || total == limit {
+        return (total, prev.iter().map(|p| p.is_some()).collect());
+      }
+      let mut cur = to;
+      while cur != from {
+        let p = prev[cur].unwrap();
+        *residual.get_mut(&(p, cur)).unwrap() -= 1;
+        *residual.get_mut(&(cur, p)).unwrap() += 1;
+        cur = p;
+      }
+      total += 1;
+    }
+  }
+
+  // Number of edge-disjoint paths between two vertices, counting at most `limit`.
+  pub fn connectivity(&self, from: usize, to: usize, limit: usize) -> usize {
+    self.max_flow(from, to, limit).0
+  }
+
+  // Minimum cut separating `from` (inside) from `to`.
+  pub fn st_cut(&self, from: usize, to: usize) -> Cut {
+    let (_, side) = self.max_flow(from, to, usize::MAX);
+    Cut::new(self, side)
+  }
+
+  // Global minimum cut (Stoer-Wagner), None for fewer than two vertices.
+  pub fn min_cut(&self) -> Option<Cut> {
+    let n = self.names.len();
+    let mut adj = self.conn.iter().map(|a| {
+      let mut weights = HashMap::<usize, usize>::new();
+      for &j in a { *weights.entry(j).or_default() += 1; }
+      weights
+    }).collect::<Vec<_>>();
+    let mut members = (0..n).map(|i| vec![i]).collect::<Vec<_>>();
+    let mut alive = (0..n).collect::<HashSet<_>>();
+    let mut best: Option<(usize, Vec<usize>)> = None;
+    while alive.len() > 1 {
+      // Maximum adjacency order; the last two vertices are merged.
+      let first = *alive.iter().next().unwrap();
+      let mut weight = HashMap::<usize, usize>::from([(first, 0)]);
+      let mut added = HashSet::<usize>::new();
+      let mut heap = BinaryHeap::from([(0, first)]);
+      let (mut s, mut t, mut cut) = (first, first, 0);
+      while let Some((w, v)) = heap.pop() {
+        if added.contains(&v) || weight[&v] != w { continue; }
+        added.insert(v);
+        (s, t, cut) = (t, v, w);
+        for (&u, &x) in &adj[v] {
+          if added.contains(&u) { continue; }
+          let e = weight.entry(u).or_default();
+          *e += x;
+          heap.push((*e, u));
+        }
+        if added.len() == alive.len() { break; }
+      }
+      if added.len() < alive.len() {
+        // Disconnected: the component found so far is a cut of weight zero.
+        best = Some((0, added.iter().flat_map(|&v| members[v].clone()).collect()));
+        break;
+      }
+      if best.as_ref().is_none_or(|(w, _)| cut < *w) {
+        best = Some((cut, members[t].clone()));
+      }
+      let moved = std::mem::take(&mut members[t]);
+      members[s].extend(moved);
+      for (u, x) in std::mem::take(&mut adj[t]) {
+        adj[u].remove(&t);
+        if u != s {
+          *adj[s].entry(u).or_default() += x;
+          *adj[u].entry(s).or_default() += x;
+        }
+      }
+      alive.remove(&t);
+    }
+    best.map(|(_, inside)| {
+      let mut side = vec![false; n];
+      for v in inside { side[v] = true; }
+      Cut::new(self, side)
+    })
+  }
+
+  // Greedy groups of vertices with at least `count` edge-disjoint paths
+  // to the first vertex of their group.
+  #[allow(dead_code)]
+  pub fn find_cliques(&self, count: usize) -> Vec<HashSet<usize>> {
+    let mut result: Vec<HashSet<usize>> = vec![];
+    let mut visited = HashSet::<usize>::new();
+    for i in 0..self.names.len() {
+      if visited.contains(&i) { continue; }
+      let mut connect: Vec<usize> = vec![i];
+      for j in i+1..self.names.len() {
+        if visited.contains(&j) { continue; }
+        if self.connectivity(i, j, count) >= count { connect.push(j); }
+      }
+      if connect.len() > 1 {
+        visited.extend(connect.iter().cloned());
+        result.push(HashSet::from_iter(connect));
+      }
+    }
+    result
+  }
+}
@@ -1,4 +1,4 @@
-use std::cmp;
+use crate::piecewise::Piecewise;
 
 #[derive(Debug)]
 struct ARange {
@@ -23,9 +23,6 @@ impl ARange {
   fn left(&self) -> u64 { self.from }
   fn right(&self) -> u64 { self.from + self.size - 1 }
   fn convert(&self, value: u64) -> u64 { self.to + value - self.from }
-  fn intersects(&self, range: (u64, u64)) -> bool {
-    !(range.0 > self.right() || range.1 < self.left())
-  }
 }
 
 impl AMap {
@@ -35,24 +32,9 @@ impl AMap {
     }).next().map_or(source, |range| range.convert(source))
   }
 
-  fn rmap(&self, source: (u64, u64)) -> Vec<(u64, u64)> {
-    let mut results = Vec::<(u64, u64)>::new();
-    let mut parts = vec![source];
-    for range in &self.ranges {
-      parts = parts.into_iter().flat_map(|src| {
-        if range.intersects(src) {
-          let l = cmp::max(src.0, range.left());
-          let r = cmp::min(src.1, range.right());
-          results.push((range.convert(l), range.convert(r)));
-          let mut rest = vec![];
-          if l != src.0 { rest.push((src.0, l - 1)); }
-          if r != src.1 { rest.push((r + 1, src.1)); }
-          rest
-        } else { vec![src] }
-      }).collect();
-    }
-    results.extend(parts);
-    results
+  fn piecewise(&self) -> Piecewise {
+    let ranges = self.ranges.iter().map(|r| (r.to, r.from, r.size)).collect::<Vec<_>>();
+    Piecewise::from_ranges(&ranges)
   }
 }
 
@@ -85,19 +67,18 @@ impl Almanac {
     self.seeds.iter().map(|&x| self.map(x)).collect()
   }
 
-  fn rmap(&self, seed_range: (u64, u64)) -> Vec<(u64, u64)> {
-    self.maps.iter().fold(vec![seed_range], |a, m| {
-      a.iter().flat_map(|&v| m.rmap(v)).collect()
-    })
+  // All stages collapsed into a single seed to location map.
+  fn compose(&self) -> Piecewise {
+    self.maps.iter().fold(Piecewise::identity(), |acc, m| acc.then(&m.piecewise()))
+  }
+
+  fn seed_ranges(&self) -> Vec<(u64, u64)> {
+    self.seeds.chunks(2).map(|p| (p[0], p[0] + p[1] - 1)).collect()
   }
 
   fn rmap_all(&self) -> Vec<u64> {
-    let get_range = |p, n| (p, p + n - 1);
-    let get_from = |i| get_range(self.seeds[i], self.seeds[i + 1]);
-    (0..self.seeds.len()).step_by(2)
-      .map(|i| self.rmap(get_from(i)))
-      .map(|x| x.iter().min().unwrap().0)
-      .collect()
+    let map = self.compose();
+    self.seed_ranges().into_iter().map(|r| map.min_image(&[r]).unwrap()).collect()
   }
 }
 
@@ -110,6 +91,9 @@ pub fn run(content: &str) {
 
 #[cfg(test)]
 mod tests {
+  use crate::piecewise::Piecewise;
+  use crate::xorshift::XorShift;
+
   const TEST: &str = "\
 seeds: 79 14 55 13
 
@@ -156,4 +140,60 @@ humidity-to-location map:
     let almanac = super::Almanac::parse(TEST);
     assert_eq!(almanac.rmap_all(), [46, 56]);
   }
+
+  #[test]
+  fn composed() {
+    let almanac = super::Almanac::parse(TEST);
+    let map = almanac.compose();
+    for seed in 0..120 {
+      assert_eq!(map.apply(seed), almanac.map(seed));
+    }
+    // Every stage is a permutation, so the whole chain can be undone.
+    let inverse = map.inverse().unwrap();
+    assert_eq!(inverse.apply(46), 82);
+    assert_eq!(map.preimage((46, 46)), [(82, 82)]);
+    assert_eq!(map.then(&inverse), Piecewise::identity());
+  }
+
+  // Random almanacs with non-overlapping source ranges, checked against
+  // the stage by stage pointwise map.
+  #[test]
+  fn random() {
+    let mut rng = XorShift::new(0x2545f4914f6cdd1d);
+    let mut next = |n: u64| rng.below(n);
+    for _ in 0..200 {
+      let stages = 1 + next(7) as usize;
+      let maps = (0..stages).map(|i| {
+        let mut start = next(20);
+        let ranges = (0..next(5)).map(|_| {
+          let range = super::ARange { from: start, to: next(200), size: 1 + next(30) };
+          start += range.size + next(10);
+          range
+        }).collect();
+        super::AMap { _name: format!("stage {}", i), ranges }
+      }).collect();
+      let almanac = super::Almanac { seeds: vec![], maps };
+      let map = almanac.compose();
+      for x in 0..300 {
+        let y = almanac.map(x);
+        assert_eq!(map.apply(x), y);
+        assert!(map.preimage((y, y)).iter().any(|&(a, b)| a <= x && x <= b));
+      }
+      for _ in 0..20 {
+        let first = next(250);
+        let range = (first, first + next(50));
+        let expected = (range.0..=range.1).map(|x| almanac.map(x)).min();
+        assert_eq!(map.min_image(&[range]), expected);
+        let mut image = map.image(range).into_iter()
+          .flat_map(|(a, b)| a..=b).collect::<Vec<_>>();
+        let mut pointwise = (range.0..=range.1).map(|x| almanac.map(x)).collect::<Vec<_>>();
+        image.sort();
+        pointwise.sort();
+        assert_eq!(image, pointwise);
+      }
+      if let Some(inverse) = map.inverse() {
+        for x in 0..300 { assert_eq!(inverse.apply(map.apply(x)), x); }
+      }
+    }
+  }
 }
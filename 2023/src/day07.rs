@@ -1,19 +1,12 @@
 use std::cmp::Ordering;
 use std::fmt;
 
-const CARDS: &str = "*_23456789TJQKA";
-
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct Card {
-  value: u8,
-}
-
-#[derive(Clone, Debug, Eq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct Hand {
-  cards: [Card; 5],
+  cards: [char; 5],
 }
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 enum Rank {
   High,
   OnePair,
@@ -24,102 +17,165 @@ enum Rank {
   Five,
 }
 
-impl Card {
-  fn parse(label: char, joker: bool) -> Card {
-    if joker && label == 'J' { return Card { value: 0 }; }
-    Card { value: CARDS.find(label).unwrap() as u8 }
-  }
+// How hands compare: card labels from weakest to strongest, and the labels
+// that stand in for whatever card makes the best rank.
+#[derive(Clone, Debug)]
+struct Policy {
+  order: Vec<char>,
+  wild: Vec<char>,
+}
+
+// Why two hands compare the way they do.
+#[derive(Clone, Debug, PartialEq)]
+enum Reason {
+  Rank(Rank, Rank),
+  Card(usize, char, char),
+  Same,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Explanation {
+  ordering: Ordering,
+  reason: Reason,
 }
 
 impl Hand {
-  fn parse(labels: &str, joker: bool) -> Hand {
-    let cards: Vec<Card> = labels.chars()
-      .map(|c| Card::parse(c, joker)).collect();
+  fn parse(labels: &str) -> Hand {
+    let cards: Vec<char> = labels.chars().collect();
     Hand { cards: cards.try_into().unwrap() }
   }
 
-  fn parse_game(text: &str, joker: bool) -> Vec<(Hand, u32)> {
+  fn parse_game(text: &str) -> Vec<(Hand, u32)> {
     text.lines().map(|line| {
       let (s1, s2) = line.split_once(' ').unwrap();
-      (Hand::parse(s1, joker), s2.parse::<u32>().unwrap())
+      (Hand::parse(s1), s2.parse::<u32>().unwrap())
     }).collect()
   }
+}
 
-  fn get_rank(&self) -> Rank {
-    let mut count = [0_u8; 15];
-    for card in self.cards {
-      count[card.value as usize] += 1;
-    }
-    let wild = count[0] as usize;
-    let mut hist = [0_u8; 6];
-    for c in count.into_iter().skip(2) {
-      hist[c as usize] += 1;
-    }
-    if hist[5 - wild] != 0 {
-      return Rank::Five;
+impl Policy {
+  fn new(order: &str, wild: &str) -> Result<Policy, String> {
+    let order: Vec<char> = order.chars().collect();
+    let wild: Vec<char> = wild.chars().collect();
+    if let Some(c) = order.iter().enumerate().find(|&(i, c)| order[..i].contains(c)) {
+      return Err(format!("card {} appears twice in the order", c.1));
     }
-    if hist[4 - wild] != 0 {
-      return Rank::Four;
+    if let Some(c) = wild.iter().find(|c| !order.contains(c)) {
+      return Err(format!("wild card {} is not in the order", c));
     }
-    if hist[3 - wild] != 0 {
-      let has_pair = hist[2] >= (if wild == 1 {2} else {1});
-      return if has_pair {Rank::FullHouse} else {Rank::Three};
+    Ok(Policy { order, wild })
+  }
+
+  fn standard() -> Policy {
+    Policy::new("23456789TJQKA", "").unwrap()
+  }
+
+  fn jokers() -> Policy {
+    Policy::new("J23456789TQKA", "J").unwrap()
+  }
+
+  fn value(&self, card: char) -> usize {
+    self.order.iter().position(|&c| c == card)
+      .unwrap_or_else(|| panic!("unknown card {}", card))
+  }
+
+  // Wild cards join the largest group of equal cards.
+  fn rank(&self, hand: &Hand) -> Rank {
+    let mut counts = vec![0; self.order.len()];
+    let mut wild = 0;
+    for &card in &hand.cards {
+      if self.wild.contains(&card) { wild += 1; } else { counts[self.value(card)] += 1; }
     }
-    if hist[2 - wild] != 0 {
-      let has_pair = hist[2] >= (if wild == 0 {2} else {1});
-      return if has_pair {Rank::TwoPair} else {Rank::OnePair};
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts[0] += wild;
+    match counts[..] {
+      [5, ..] => Rank::Five,
+      [4, ..] => Rank::Four,
+      [3, 2, ..] => Rank::FullHouse,
+      [3, ..] => Rank::Three,
+      [2, 2, ..] => Rank::TwoPair,
+      [2, ..] => Rank::OnePair,
+      _ => Rank::High,
     }
-    return Rank::High;
   }
-}
 
-impl Ord for Hand {
-  fn cmp(&self, other: &Self) -> Ordering {
-    let a = (self.get_rank(), &self.cards);
-    let b = (other.get_rank(), &other.cards);
-    a.cmp(&b)
+  fn key(&self, hand: &Hand) -> (Rank, [usize; 5]) {
+    (self.rank(hand), hand.cards.map(|c| self.value(c)))
   }
-}
 
-impl PartialEq for Hand {
-  fn eq(&self, other: &Self) -> bool {
-    self.cmp(other) == Ordering::Equal
+  fn compare(&self, a: &Hand, b: &Hand) -> Ordering {
+    self.key(a).cmp(&self.key(b))
   }
-}
 
-impl PartialOrd for Hand {
-  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-    Some(self.cmp(other))
+  #[allow(dead_code)]
+  fn explain(&self, a: &Hand, b: &Hand) -> Explanation {
+    let (ra, rb) = (self.rank(a), self.rank(b));
+    let reason = if ra != rb {
+      Reason::Rank(ra, rb)
+    } else {
+      match (0..5).find(|&i| a.cards[i] != b.cards[i]) {
+        Some(i) => Reason::Card(i, a.cards[i], b.cards[i]),
+        None => Reason::Same,
+      }
+    };
+    Explanation { ordering: self.compare(a, b), reason }
   }
 }
 
-impl fmt::Display for Card {
+impl fmt::Display for Rank {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let label = CARDS.as_bytes()[self.value as usize] as char;
-    write!(f, "{}", label)
+    let name = match self {
+      Rank::High => "high card",
+      Rank::OnePair => "one pair",
+      Rank::TwoPair => "two pair",
+      Rank::Three => "three of a kind",
+      Rank::FullHouse => "full house",
+      Rank::Four => "four of a kind",
+      Rank::Five => "five of a kind",
+    };
+    write!(f, "{}", name)
   }
 }
 
 impl fmt::Display for Hand {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "{}{}{}{}{}", self.cards[0], self.cards[1], self.cards[2],
-                            self.cards[3], self.cards[4])
+    write!(f, "{}", self.cards.iter().collect::<String>())
+  }
+}
+
+impl fmt::Display for Explanation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let word = match self.ordering {
+      Ordering::Less => "loses to",
+      Ordering::Equal => "ties with",
+      Ordering::Greater => "beats",
+    };
+    match self.reason {
+      Reason::Rank(a, b) => write!(f, "{} {} {}", a, word, b),
+      Reason::Card(i, a, b) => write!(f, "same rank, card {}: {} {} {}", i + 1, a, word, b),
+      Reason::Same => write!(f, "same cards"),
+    }
   }
 }
 
-fn score(mut game: Vec<(Hand, u32)>) -> u32 {
-  game.sort();
+fn score(mut game: Vec<(Hand, u32)>, policy: &Policy) -> u32 {
+  game.sort_by(|a, b| policy.compare(&a.0, &b.0));
   game.iter().enumerate().map(|(k, v)| (k + 1) as u32 * v.1).sum()
 }
 
 pub fn run(content: &str) {
-  let res1 = score(Hand::parse_game(content, false));
-  let res2 = score(Hand::parse_game(content, true));
+  let game = Hand::parse_game(content);
+  let res1 = score(game.clone(), &Policy::standard());
+  let res2 = score(game, &Policy::jokers());
   println!("{} {}", res1, res2);
 }
 
 #[cfg(test)]
 mod tests {
+  use super::{Hand, Policy};
+  use crate::xorshift::XorShift;
+  use std::cmp::Ordering;
+
   const TEST: &str = "\
 32T3K 765
 T55J5 684
@@ -129,13 +185,70 @@ QQQJA 483";
 
   #[test]
   fn small() {
-    let game = super::Hand::parse_game(TEST, false);
-    assert_eq!(super::score(game), 6440);
+    let game = Hand::parse_game(TEST);
+    assert_eq!(super::score(game, &Policy::standard()), 6440);
   }
 
   #[test]
   fn large() {
-    let game = super::Hand::parse_game(TEST, true);
-    assert_eq!(super::score(game), 5905);
+    let game = Hand::parse_game(TEST);
+    assert_eq!(super::score(game, &Policy::jokers()), 5905);
+  }
+
+  #[test]
+  fn explain() {
+    let explain = |policy: &Policy, a: &str, b: &str| {
+      policy.explain(&Hand::parse(a), &Hand::parse(b)).to_string()
+    };
+    let (standard, jokers) = (Policy::standard(), Policy::jokers());
+    assert_eq!(explain(&standard, "KK677", "KTJJT"), "same rank, card 2: K beats T");
+    assert_eq!(explain(&standard, "T55J5", "32T3K"), "three of a kind beats one pair");
+    assert_eq!(explain(&jokers, "KTJJT", "QQQJA"), "same rank, card 1: K beats Q");
+    assert_eq!(explain(&jokers, "JKKK2", "QQQQ2"), "same rank, card 1: J loses to Q");
+    assert_eq!(explain(&jokers, "JJJJJ", "AAAA2"), "five of a kind beats four of a kind");
+    assert_eq!(explain(&standard, "AAAAA", "AAAAA"), "same cards");
+    // Twos wild and ranked above aces.
+    let custom = Policy::new("3456789TJQKA2", "2").unwrap();
+    assert_eq!(explain(&custom, "2345A", "AAKKQ"), "one pair loses to two pair");
+    assert_eq!(explain(&custom, "22AKQ", "AAAKQ"), "same rank, card 1: 2 beats A");
+    assert!(Policy::new("2234", "").is_err());
+    assert!(Policy::new("234", "J").is_err());
+  }
+
+  // Ranking is a total order for every policy: antisymmetric, transitive,
+  // and equal only for identical hands.
+  #[test]
+  fn total_order() {
+    let mut rng = XorShift::new(0x853c49e6748fea9b);
+    let policies = [
+      Policy::standard(),
+      Policy::jokers(),
+      Policy::new("AKQJT98765432", "").unwrap(),
+      Policy::new("23456789TJQKA", "27K").unwrap(),
+      Policy::new("J23456789TQKA", "23456789TJQKA").unwrap(),
+    ];
+    for policy in &policies {
+      // Few distinct cards so that ranks and ties come up often.
+      let hands = (0..60).map(|_| {
+        let cards = (0..5).map(|_| policy.order[rng.below(4) as usize * 3]).collect::<String>();
+        Hand::parse(&cards)
+      }).collect::<Vec<_>>();
+      for a in &hands {
+        for b in &hands {
+          let ab = policy.compare(a, b);
+          assert_eq!(ab, policy.compare(b, a).reverse());
+          assert_eq!(ab == Ordering::Equal, a == b);
+          assert_eq!(policy.explain(a, b).ordering, ab);
+          for c in &hands {
+            if ab != Ordering::Greater && policy.compare(b, c) != Ordering::Greater {
+              assert_ne!(policy.compare(a, c), Ordering::Greater);
+            }
+          }
+        }
+      }
+      let mut sorted = hands.clone();
+      sorted.sort_by(|a, b| policy.compare(a, b));
+      assert!(sorted.windows(2).all(|w| policy.compare(&w[0], &w[1]) != Ordering::Greater));
+    }
   }
 }
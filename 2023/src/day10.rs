@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use crate::polygon::Polygon;
 
 type Point = (i32, i32);
 
@@ -19,15 +19,6 @@ struct Grid {
   start: Point,
 }
 
-fn get_side((x1, y1): Point, (x2, y2): Point, left: bool) -> Point {
-  let delta = if left {1} else {-1};
-  if (x2, y2) == (x1 + 1, y1) { return (x2, y2 - delta); }
-  if (x2, y2) == (x1, y1 + 1) { return (x2 + delta, y2); }
-  if (x2, y2) == (x1 - 1, y1) { return (x2, y2 + delta); }
-  if (x2, y2) == (x1, y1 - 1) { return (x2 - delta, y2); }
-  panic!("incorrect input");
-}
-
 impl Cell {
   fn endpoints(&self, (x, y): Point) -> Option<[Point; 2]> {
     match self {
@@ -100,36 +91,11 @@ impl Grid {
       .max().unwrap().1
   }
 
-  fn get_adjacent(&self, left: bool) -> HashSet<Point> {
-    let mut result = HashSet::<Point>::new();
-    let mut path = self.longest();
-    let path_set = HashSet::<Point>::from_iter(path.iter().cloned());
-    path.push(self.start);
-    for p in path.windows(2) {
-      let mut search = vec![
-        get_side(p[0], p[1], left),
-        get_side(p[1], p[0], !left)
-      ];
-      while let Some(cur) = search.pop() {
-        if !self.is_valid(cur) || path_set.contains(&cur) ||
-          result.contains(&cur) { continue; }
-        let (x, y) = cur;
-        for next in [(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)] {
-          search.push(next);
-        }
-        result.insert(cur);
-      }
-    }
-    result
-  }
-
-  fn count_inner(&self) -> usize {
-    let s1 = self.get_adjacent(false);
-    let s2 = self.get_adjacent(true);
-    [s1, s2].iter().filter(|s| s.iter().all(|&(x, y)| {
-        x != 0 && x != self.width() - 1 &&
-        y != 0 && y != self.height() - 1
-      })).next().unwrap().len()
+  // Tiles enclosed by the loop: its cells are the lattice points on the
+  // outline of a polygon, so Pick's theorem gives the ones inside.
+  fn count_inner(&self) -> i128 {
+    let path = self.longest();
+    Polygon::new(path.into_iter().map(|(x, y)| (x as i64, y as i64)).collect()).interior_points()
   }
 }
 
@@ -182,4 +148,17 @@ L7JLJL-JLJLJL--JLJ.L";
     assert_eq!(super::Grid::parse(TEST_2).count_inner(), 8);
     assert_eq!(super::Grid::parse(TEST_3).count_inner(), 10);
   }
+
+  #[test]
+  fn polygon() {
+    use crate::polygon::{Location, Polygon};
+    let path = super::Grid::parse(TEST_1).longest();
+    let polygon = Polygon::new(path.iter().map(|&(x, y)| (x as i64, y as i64)).collect());
+    assert!(polygon.is_simple());
+    assert_eq!(polygon.boundary_points(), 16);
+    assert_eq!(polygon.interior_points(), 1);
+    assert_eq!(polygon.locate((2, 2)), Location::Inside);
+    assert_eq!(polygon.locate((0, 2)), Location::Boundary);
+    assert_eq!(polygon.locate((0, 0)), Location::Outside);
+  }
 }
@@ -1,24 +1,12 @@
-use std::collections::HashMap;
+use crate::nonogram::{Cell, Line};
 use std::iter;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Cell {
-  Operational,
-  Damaged,
-  Unknown,
-}
-
 #[derive(Debug)]
 struct Spring {
   cells: Vec<Cell>,
   groups: Vec<usize>,
 }
 
-#[derive(Default)]
-struct Solution {
-  cache: HashMap<(usize, usize, bool), usize>,
-}
-
 impl Spring {
   fn parse(text: &str) -> Spring {
     let (s1, s2) = text.split_once(' ').unwrap();
@@ -43,32 +31,12 @@ impl Spring {
     Spring { cells, groups }
   }
 
-  fn arrangements(&self) -> usize {
-    let mut solution = Solution::default();
-    solution.descend(&self.cells[..], &self.groups[..], false)
+  fn line(&self) -> Line {
+    Line::new(&self.cells, &self.groups)
   }
-}
-
-impl Solution {
-  fn descend(&mut self, cells: &[Cell], groups: &[usize],
-             restrict: bool) -> usize {
-    let some_is = |a: &[Cell], x: Cell| a.iter().any(|&c| c == x);
-    if groups.is_empty() { return !some_is(cells, Cell::Damaged) as usize; }
-    let next = groups[0];
-    if next > cells.len() { return 0; }
 
-    let key = (cells.len(), groups.len(), restrict);
-    if let Some(res) = self.cache.get(&key) { return *res; }
-    let mut res = 0_usize;
-    if !matches!(cells[0], Cell::Damaged) {
-      res += self.descend(&cells[1..], groups, false);
-    }
-    if !matches!(cells[0], Cell::Operational) && !restrict &&
-       !some_is(&cells[..next], Cell::Operational) {
-      res += self.descend(&cells[next..], &groups[1..], true);
-    }
-    self.cache.insert(key, res);
-    res
+  fn arrangements(&self) -> usize {
+    usize::try_from(self.line().count()).expect("too many arrangements")
   }
 }
 
@@ -81,6 +49,9 @@ pub fn run(content: &str) {
 
 #[cfg(test)]
 mod tests {
+  use crate::nonogram::{Cell, Nonogram};
+  use crate::xorshift::XorShift;
+
   const TEST: &str = "\
 ???.### 1,1,3
 .??..??...?##. 1,1,3
@@ -103,4 +74,77 @@ mod tests {
       .map(|x| x.arrangements()).collect::<Vec<usize>>();
     assert_eq!(test, [1, 16384, 1, 16, 2500, 506250]);
   }
+
+  fn show(cells: &[Cell]) -> String {
+    cells.iter().map(|c| match c {
+      Cell::Operational => '.',
+      Cell::Damaged => '#',
+      Cell::Unknown => '?',
+    }).collect()
+  }
+
+  #[test]
+  fn enumerate() {
+    let line = super::Spring::parse(".??..??...?##. 1,1,3").line();
+    let all = line.arrangements().map(|a| show(&a)).collect::<Vec<_>>();
+    assert_eq!(all, [
+      "..#...#...###.",
+      "..#..#....###.",
+      ".#....#...###.",
+      ".#...#....###.",
+    ]);
+    for text in TEST.lines() {
+      let spring = super::Spring::parse(text);
+      let line = spring.unfold(2).line();
+      assert_eq!(line.arrangements().count() as u128, line.count());
+    }
+    assert_eq!(super::Spring::parse("#.# 2").line().arrangements().next(), None);
+  }
+
+  #[test]
+  fn sample() {
+    let mut rng = XorShift::new(0x9e3779b97f4a7c15);
+    let mut random = |n: u128| rng.next() as u128 % n;
+    let line = super::Spring::parse("?###???????? 3,2,1").line();
+    let all = line.arrangements().collect::<Vec<_>>();
+    let mut seen = vec![0; all.len()];
+    for _ in 0..10000 {
+      let a = line.sample(&mut random).unwrap();
+      seen[all.iter().position(|b| *b == a).unwrap()] += 1;
+    }
+    // Ten arrangements, about a thousand draws each.
+    assert!(seen.iter().all(|&n| (850..1150).contains(&n)), "{:?}", seen);
+  }
+
+  #[test]
+  fn deduce() {
+    let line = super::Spring::parse("?###???????? 3,2,1").line();
+    assert_eq!(show(&line.deduce().unwrap()), ".###.???????");
+    for text in TEST.lines() {
+      let spring = super::Spring::parse(text).unfold(2);
+      let line = spring.line();
+      let all = line.arrangements().collect::<Vec<_>>();
+      let expected = (0..spring.cells.len()).map(|i| {
+        if all.iter().all(|a| a[i] == all[0][i]) {all[0][i]} else {Cell::Unknown}
+      }).collect::<Vec<_>>();
+      assert_eq!(line.deduce(), Some(expected));
+    }
+    assert_eq!(super::Spring::parse("##.? 3").line().deduce(), None);
+  }
+
+  #[test]
+  fn nonogram() {
+    let clues = |s: &str| s.split(' ').map(|g| {
+      g.split(',').filter(|x| !x.is_empty()).map(|x| x.parse().unwrap()).collect()
+    }).collect::<Vec<Vec<usize>>>();
+    let puzzle = Nonogram { rows: clues("3 1,1 5 1 1,1"), cols: clues("1 3,1 1,2 3 1,1") };
+    let solutions = puzzle.solve(2);
+    assert_eq!(solutions.len(), 1);
+    assert_eq!(solutions[0].to_string(), ".###.\n.#.#.\n#####\n..#..\n.#..#\n");
+    // Two diagonals fit the same clues.
+    let puzzle = Nonogram { rows: clues("1 1"), cols: clues("1 1") };
+    assert_eq!(puzzle.solve(5).len(), 2);
+    let puzzle = Nonogram { rows: clues("2 "), cols: clues("1 ") };
+    assert!(puzzle.solve(1).is_empty());
+  }
 }
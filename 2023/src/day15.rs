@@ -1,3 +1,5 @@
+use crate::holiday::{self, HolidayMap};
+use std::env;
 
 #[derive(Debug)]
 enum Operation {
@@ -5,11 +7,6 @@ enum Operation {
   Remove(String),
 }
 
-#[derive(Debug)]
-struct HashState {
-  state: [Vec<(String, u8)>; 256],
-}
-
 impl Operation {
   fn parse(text: &str) -> Operation {
     if text.ends_with('-') {
@@ -19,71 +16,99 @@ impl Operation {
     Operation::Add(String::from(s1), s2.parse::<u8>().unwrap())
   }
 
-  fn index(&self) -> usize {
-    make_hash(match self {
-      Operation::Add(s, _) => s,
-      Operation::Remove(s) => s,
-    }) as usize
-  }
-}
-
-impl HashState {
-  fn new() -> HashState {
-    HashState { state: std::array::from_fn(|_| vec![]) }
-  }
-
-  fn process(&mut self, op: Operation) {
-    let bucket = &mut self.state[op.index()];
-    match op {
-      Operation::Add(s, n) => match bucket.iter().position(|x| x.0 == s) {
-        Some(i) => bucket[i].1 = n,
-        None => bucket.push((s, n)),
-      },
-      Operation::Remove(s) => bucket.retain(|x| x.0 != s),
+  fn apply(self, lenses: &mut HolidayMap<String, u8>) {
+    match self {
+      Operation::Add(s, n) => { lenses.insert(s, n); },
+      Operation::Remove(s) => { lenses.remove(&s); },
     }
   }
+}
 
-  fn process_all(&mut self, ops: Vec<Operation>) {
-    ops.into_iter().for_each(|op| self.process(op));
-  }
+fn process_all(ops: Vec<Operation>) -> HolidayMap<String, u8> {
+  let mut lenses = HolidayMap::new();
+  ops.into_iter().for_each(|op| op.apply(&mut lenses));
+  lenses
+}
 
-  fn power(&self) -> usize {
-    self.state.iter().enumerate().map(
-      |(i, a)| (i + 1) * a.iter().enumerate().map(
-      |(j, b)| (j + 1) * b.1 as usize).sum::<usize>()
-    ).sum()
-  }
+// Box contents after every step, as in the puzzle statement.
+fn trace(text: &str) -> String {
+  let mut lenses = HolidayMap::new();
+  text.split(',').map(|s| {
+    Operation::parse(s).apply(&mut lenses);
+    format!("After \"{}\":\n{}", s, lenses)
+  }).collect::<Vec<_>>().join("\n")
 }
 
-fn make_hash(text: &str) -> u32 {
-  text.chars().fold(0_u32, |a, b| {
-    (a + b as u32) * 17 % 256
-  })
+fn power(lenses: &HolidayMap<String, u8>) -> usize {
+  lenses.boxes().map(
+    |(i, a)| (i + 1) * a.iter().enumerate().map(
+    |(j, b)| (j + 1) * b.1 as usize).sum::<usize>()
+  ).sum()
 }
 
 pub fn run(content: &str) {
   let raw: Vec<&str> = content.trim_end().split(',').collect();
-  let mut state = HashState::new();
-  state.process_all(raw.iter().map(|&s| Operation::parse(s)).collect());
-  let res1 = raw.iter().map(|&s| make_hash(s)).sum::<u32>();
-  let res2 = state.power();
+  let lenses = process_all(raw.iter().map(|&s| Operation::parse(s)).collect());
+  let res1 = raw.iter().map(|&s| holiday::hash(s) as u32).sum::<u32>();
+  let res2 = power(&lenses);
   println!("{} {}", res1, res2);
+  if env::args().any(|a| a == "--trace") {
+    println!("{}", trace(content.trim_end()));
+  }
 }
 
 #[cfg(test)]
 mod tests {
+  use crate::holiday::{self, HolidayMap};
+
   const TEST: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
   #[test]
   fn small() {
-    let hash = TEST.split(',').map(super::make_hash).collect::<Vec<u32>>();
+    let hash = TEST.split(',').map(holiday::hash).collect::<Vec<u8>>();
     assert_eq!(hash, [30, 253, 97, 47, 14, 180, 9, 197, 48, 214, 231]);
   }
 
   #[test]
   fn large() {
-    let mut state = super::HashState::new();
-    state.process_all(TEST.split(',').map(super::Operation::parse).collect());
-    assert_eq!(state.power(), 145);
+    let lenses = super::process_all(TEST.split(',').map(super::Operation::parse).collect());
+    assert_eq!(super::power(&lenses), 145);
+  }
+
+  #[test]
+  fn trace() {
+    let trace = super::trace(TEST);
+    let steps = trace.split("\n\n").collect::<Vec<_>>();
+    assert_eq!(steps.len(), 11);
+    assert_eq!(steps[0], "After \"rn=1\":\nBox 0: [rn 1]");
+    assert_eq!(steps[4], "After \"qp-\":\nBox 0: [rn 1] [cm 2]");
+    assert_eq!(steps[10], "After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n");
+  }
+
+  #[test]
+  fn map() {
+    let mut map = HolidayMap::<&str, u32>::new();
+    assert!(map.is_empty());
+    assert_eq!(map.insert("rn", 1), None);
+    assert_eq!(map.insert("cm", 2), None);
+    assert_eq!(map.insert("qp", 3), None);
+    assert_eq!(map.insert("rn", 4), Some(1));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get("rn"), Some(&4));
+    assert_eq!(map.get("ot"), None);
+    *map.get_mut("qp").unwrap() += 10;
+    // rn and cm share box 0 and keep their insertion order.
+    assert_eq!(map.iter().collect::<Vec<_>>(), [(&"rn", &4), (&"cm", &2), (&"qp", &13)]);
+    assert_eq!(map.remove("rn"), Some(4));
+    assert_eq!(map.remove("rn"), None);
+    assert!(!map.contains_key("rn") && map.contains_key("cm"));
+    assert_eq!(map.boxes().map(|(i, b)| (i, b.len())).collect::<Vec<_>>(), [(0, 1), (1, 1)]);
+    assert_eq!(map.len(), 2);
+
+    let mut bytes = HolidayMap::<Vec<u8>, char>::new();
+    bytes.insert(b"HASH".to_vec(), 'x');
+    assert_eq!(holiday::hash("HASH"), 52);
+    assert_eq!(bytes.boxes().next().map(|(i, _)| i), Some(52));
+    assert_eq!(bytes.get(b"HASH"), Some(&'x'));
   }
 }
@@ -1,8 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::env;
 
 type Point = (u32, u32);
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u8)]
 enum Direction {
   Up,
   Down,
@@ -19,7 +21,7 @@ enum Cell {
   SplitterV,
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 struct Beam {
   pos: Point,
   dir: Direction,
@@ -29,6 +31,18 @@ struct Field {
   cells: Vec<Vec<Cell>>,
 }
 
+// Beam states (tile and direction of travel) condensed into strongly
+// connected components. Energized tiles are kept as bit sets for the
+// components of the entries asked for; the others are dropped as soon as
+// every component leading to them is done.
+struct Tracer<'a> {
+  field: &'a Field,
+  component: Vec<usize>,
+  energized: HashMap<usize, Vec<u64>>,
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
 impl Direction {
   fn vertical(&self) -> bool {
     matches!(self, Direction::Up | Direction::Down)
@@ -36,6 +50,14 @@ impl Direction {
   fn horizontal(&self) -> bool {
     matches!(self, Direction::Left | Direction::Right)
   }
+  fn arrow(&self) -> char {
+    match self {
+      Direction::Up => '^',
+      Direction::Down => 'v',
+      Direction::Left => '<',
+      Direction::Right => '>',
+    }
+  }
 }
 
 impl Cell {
@@ -60,6 +82,16 @@ impl Cell {
       Cell::SplitterV => vec![Direction::Up, Direction::Down],
     }
   }
+
+  fn symbol(&self) -> char {
+    match self {
+      Cell::Empty => '.',
+      Cell::MirrorL => '/',
+      Cell::MirrorR => '\\',
+      Cell::SplitterH => '-',
+      Cell::SplitterV => '|',
+    }
+  }
 }
 
 impl Field {
@@ -93,7 +125,7 @@ impl Field {
     }
   }
 
-  fn travel(&self, init: Beam) -> usize {
+  fn visit(&self, init: Beam) -> HashSet<Beam> {
     let mut visited = HashSet::<Beam>::new();
     let mut beams = vec![init];
     while let Some(beam) = beams.pop() {
@@ -106,10 +138,32 @@ impl Field {
       }
       visited.insert(beam);
     }
-    visited.into_iter().map(|x| x.pos).collect::<HashSet<Point>>().len()
+    visited
   }
 
-  fn travel_all(&self) -> usize {
+  fn travel(&self, init: Beam) -> usize {
+    self.visit(init).into_iter().map(|x| x.pos).collect::<HashSet<Point>>().len()
+  }
+
+  // The map with the beams of one entry drawn over the empty tiles: the
+  // direction of travel, or how many beams cross there.
+  fn render_path(&self, init: Beam) -> String {
+    let mut beams = HashMap::<Point, Vec<Direction>>::new();
+    for beam in self.visit(init) {
+      beams.entry(beam.pos).or_default().push(beam.dir);
+    }
+    (0..self.height()).map(|y| {
+      (0..self.width()).map(|x| {
+        match (self.cells[y as usize][x as usize], beams.get(&(x, y))) {
+          (Cell::Empty, Some(dirs)) if dirs.len() == 1 => dirs[0].arrow(),
+          (Cell::Empty, Some(dirs)) => char::from_digit(dirs.len() as u32, 10).unwrap(),
+          (cell, _) => cell.symbol(),
+        }
+      }).collect::<String>() + "\n"
+    }).collect()
+  }
+
+  fn entries(&self) -> Vec<Beam> {
     let it1 = (0..self.width()).flat_map(|x| [
       Beam { pos: (x, 0), dir: Direction::Down },
       Beam { pos: (x, self.height() - 1), dir: Direction::Up },
@@ -118,19 +172,165 @@ impl Field {
       Beam { pos: (0, y), dir: Direction::Right },
       Beam { pos: (self.width() - 1, y), dir: Direction::Left },
     ]);
-    it1.chain(it2).map(|b| self.travel(b)).max().unwrap()
+    it1.chain(it2).collect()
+  }
+
+  fn travel_all(&self) -> usize {
+    let entries = self.entries();
+    let tracer = Tracer::new(self, &entries);
+    entries.iter().map(|b| tracer.count(b)).max().unwrap()
+  }
+
+  // For every tile, how many edge entries energize it.
+  fn heatmap(&self) -> Vec<Vec<usize>> {
+    let entries = self.entries();
+    let tracer = Tracer::new(self, &entries);
+    let mut heat = vec![vec![0; self.width() as usize]; self.height() as usize];
+    for beam in &entries {
+      let set = tracer.energized(beam);
+      for (y, row) in heat.iter_mut().enumerate() {
+        for (x, h) in row.iter_mut().enumerate() {
+          let i = y * self.width() as usize + x;
+          *h += (set[i / 64] >> (i % 64) & 1) as usize;
+        }
+      }
+    }
+    heat
+  }
+}
+
+impl<'a> Tracer<'a> {
+  fn new(field: &'a Field, keep: &[Beam]) -> Tracer<'a> {
+    let (w, h) = (field.width() as usize, field.height() as usize);
+    let successors = |v: usize| {
+      let (tile, dir) = (v / 4, DIRECTIONS[v % 4]);
+      let pos = ((tile % w) as u32, (tile / w) as u32);
+      field.cells[pos.1 as usize][pos.0 as usize].redir(dir).into_iter().filter_map(move |d| {
+        field.advance(d, pos).map(|(x, y)| (y as usize * w + x as usize) * 4 + d as usize)
+      })
+    };
+    let (component, count) = Tracer::components(w * h * 4, successors);
+
+    // Members and successor components, and how many edges lead into each.
+    let mut members = vec![vec![]; count];
+    let mut next = vec![vec![]; count];
+    let mut pending = vec![0; count];
+    for v in 0..w * h * 4 {
+      let c = component[v];
+      members[c].push(v / 4);
+      for u in successors(v) {
+        if component[u] != c {
+          next[c].push(component[u]);
+          pending[component[u]] += 1;
+        }
+      }
+    }
+    let wanted = keep.iter().map(|b| component[Tracer::state(field, b)]).collect::<HashSet<_>>();
+    let mut sets = HashMap::<usize, Vec<u64>>::new();
+    let words = (w * h).div_ceil(64);
+    // Tarjan numbers components in reverse topological order.
+    for c in 0..count {
+      let mut set = vec![0_u64; words];
+      for &tile in &members[c] { set[tile / 64] |= 1 << (tile % 64); }
+      for &d in &next[c] {
+        for (a, b) in set.iter_mut().zip(&sets[&d]) { *a |= b; }
+        pending[d] -= 1;
+        if pending[d] == 0 && !wanted.contains(&d) { sets.remove(&d); }
+      }
+      sets.insert(c, set);
+      if pending[c] == 0 && !wanted.contains(&c) { sets.remove(&c); }
+    }
+    Tracer { field, component, energized: sets }
+  }
+
+  fn state(field: &Field, beam: &Beam) -> usize {
+    (beam.pos.1 * field.width() + beam.pos.0) as usize * 4 + beam.dir as usize
   }
+
+  // Iterative Tarjan: component of every vertex and the component count.
+  fn components<F, I>(n: usize, successors: F) -> (Vec<usize>, usize)
+  where F: Fn(usize) -> I, I: Iterator<Item = usize> {
+    const NONE: usize = usize::MAX;
+    let mut index = vec![NONE; n];
+    let mut low = vec![0; n];
+    let mut component = vec![NONE; n];
+    let (mut counter, mut count) = (0, 0);
+    let mut stack = vec![];
+    for root in 0..n {
+      if index[root] != NONE { continue; }
+      let mut calls = vec![(root, successors(root).collect::<Vec<_>>())];
+      index[root] = counter;
+      low[root] = counter;
+      counter += 1;
+      stack.push(root);
+      while let Some((v, children)) = calls.last_mut() {
+        let v = *v;
+        if let Some(u) = children.pop() {
+          if index[u] == NONE {
+            index[u] = counter;
+            low[u] = counter;
+            counter += 1;
+            stack.push(u);
+            calls.push((u, successors(u).collect()));
+          } else if component[u] == NONE {
+            low[v] = low[v].min(index[u]);
+          }
+          continue;
+        }
+        calls.pop();
+        if let Some((parent, _)) = calls.last() {
+          low[*parent] = low[*parent].min(low[v]);
+        }
+        if low[v] == index[v] {
+          while let Some(u) = stack.pop() {
+            component[u] = count;
+            if u == v { break; }
+          }
+          count += 1;
+        }
+      }
+    }
+    (component, count)
+  }
+
+  // Energized tiles of a kept entry, one bit per tile in reading order.
+  fn energized(&self, beam: &Beam) -> &[u64] {
+    &self.energized[&self.component[Tracer::state(self.field, beam)]]
+  }
+
+  fn count(&self, beam: &Beam) -> usize {
+    self.energized(beam).iter().map(|w| w.count_ones() as usize).sum()
+  }
+}
+
+// Entry counts scaled to ten levels, blank for tiles never energized.
+fn render_heatmap(heat: &[Vec<usize>]) -> String {
+  const LEVELS: &[u8] = b" .:-=+*#%@";
+  let max = heat.iter().flatten().copied().max().unwrap_or(0).max(1);
+  heat.iter().map(|row| {
+    row.iter().map(|&h| {
+      let level = if h == 0 {0} else {1 + (h - 1) * (LEVELS.len() - 1) / max};
+      LEVELS[level] as char
+    }).collect::<String>() + "\n"
+  }).collect()
 }
 
 pub fn run(content: &str) {
   let field = Field::parse(content);
-  let res1 = field.travel(Beam { pos: (0, 0), dir: Direction::Right });
+  let init = Beam { pos: (0, 0), dir: Direction::Right };
+  let res1 = field.travel(init);
   let res2 = field.travel_all();
   println!("{} {}", res1, res2);
+  if env::args().any(|a| a == "--trace") {
+    println!("{}", field.render_path(init));
+    println!("{}", render_heatmap(&field.heatmap()));
+  }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashSet;
+
   const TEST: &str = r"
 .|...\....
 |.-.\.....
@@ -155,4 +355,49 @@ mod tests {
     let test = super::Field::parse(TEST.trim());
     assert_eq!(test.travel_all(), 51);
   }
+
+  #[test]
+  fn tracer() {
+    let test = super::Field::parse(TEST.trim());
+    let entries = test.entries();
+    let tracer = super::Tracer::new(&test, &entries);
+    for beam in &entries {
+      assert_eq!(tracer.count(beam), test.travel(*beam));
+    }
+    let heat = test.heatmap();
+    let visits = entries.iter().map(|b| {
+      test.visit(*b).into_iter().map(|v| v.pos).collect::<HashSet<_>>()
+    }).collect::<Vec<_>>();
+    for (y, row) in heat.iter().enumerate() {
+      for (x, &h) in row.iter().enumerate() {
+        assert_eq!(h, visits.iter().filter(|v| v.contains(&(x as u32, y as u32))).count());
+      }
+    }
+    let max = *heat.iter().flatten().max().unwrap();
+    let map = super::render_heatmap(&heat);
+    for (line, row) in map.lines().zip(&heat) {
+      for (c, &h) in line.chars().zip(row) {
+        assert_eq!(c == '@', h == max);
+        assert_eq!(c == ' ', h == 0);
+      }
+    }
+  }
+
+  #[test]
+  fn path() {
+    let test = super::Field::parse(TEST.trim());
+    let init = super::Beam { pos: (0, 0), dir: super::Direction::Right };
+    assert_eq!(test.render_path(init), r"
+>|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../2\\..
+<->-/vv|..
+.|<<<2-|.\
+.v//.|.v..
+".trim_start());
+  }
 }
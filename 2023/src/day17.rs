@@ -1,44 +1,9 @@
-use std::collections::{HashMap, VecDeque};
-use std::slice::Iter;
-
-type Point = (u32, u32);
-
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
-enum Direction {
-  Up,
-  Down,
-  Left,
-  Right,
-}
-
-#[derive(Clone, Debug, Hash, Eq, PartialEq)]
-struct HeatState {
-  pos: Point,
-  dir: Direction,
-  step: u32,
-}
+use crate::crucible::{self, Path, Rules};
 
 struct HeatMap {
   data: Vec<Vec<u8>>,
 }
 
-impl Direction {
-  fn iterator() -> Iter<'static, Direction> {
-    static DIRECTIONS: [Direction; 4] = [
-      Direction::Up, Direction::Down, Direction::Left, Direction::Right];
-    DIRECTIONS.iter()
-  }
-
-  fn opposite(&self) -> Direction {
-    match self {
-      Direction::Up => Direction::Down,
-      Direction::Down => Direction::Up,
-      Direction::Left => Direction::Right,
-      Direction::Right => Direction::Left,
-    }
-  }
-}
-
 impl HeatMap {
   fn parse(text: &str) -> HeatMap {
     let data = text.lines().map(|s| {
@@ -50,41 +15,21 @@ impl HeatMap {
   fn width(&self) -> u32 { self.data[0].len() as u32 }
   fn height(&self) -> u32 { self.data.len() as u32 }
 
-  fn step(&self, (x, y): Point, dir: Direction) -> Option<Point> {
-    match dir {
-      Direction::Up => if y != 0 {Some((x, y - 1))} else {None},
-      Direction::Down => if y < self.height()-1 {Some((x, y + 1))} else {None},
-      Direction::Left => if x != 0 {Some((x - 1, y))} else {None},
-      Direction::Right => if x < self.width()-1 {Some((x + 1, y))} else {None},
-    }
+  fn path(&self, rules: &Rules) -> Option<Path> {
+    let (w, h) = (self.width() as usize, self.height() as usize);
+    let cost = |(x, y): crucible::Point| Some(self.data[y][x] as u64);
+    crucible::shortest_path(w, h, cost, (0, 0), (w - 1, h - 1), rules)
   }
 
   fn travel(&self, max_steps: u32, min_turn: u32) -> u32 {
-    let init = HeatState {pos: (0, 0), dir: Direction::Right, step: 0};
-    let mut queue = VecDeque::from([(init, 0_u32)]);
-    let mut best = HashMap::<HeatState, u32>::new();
-    let dest = (self.width() - 1, self.height() - 1);
-    while let Some((cur, loss)) = queue.pop_front() {
-      for &dir in Direction::iterator() {
-        if dir == cur.dir.opposite() { continue; }
-        let step = if dir == cur.dir {cur.step + 1} else {1};
-        if step > max_steps { continue; }
-        if cur.step < min_turn && dir != cur.dir && loss != 0 { continue; }
-        if let Some(new_pos) = self.step(cur.pos, dir) {
-          if new_pos == dest && step < min_turn { continue; }
-          let state = HeatState { pos: new_pos, dir, step };
-          let delta = self.data[new_pos.1 as usize][new_pos.0 as usize] as u32;
-          let new_loss = loss + delta;
-          if !best.get(&state).map(|v| *v <= new_loss).unwrap_or(false) {
-            best.insert(state.clone(), new_loss);
-            queue.push_back((state, new_loss));
-          }
-        }
-      }
-    }
-    best.iter().filter_map(|(k, v)| {
-      if k.pos == dest {Some(*v)} else {None}
-    }).min().unwrap()
+    let rules = Rules::crucible(min_turn as usize, max_steps as usize);
+    self.path(&rules).unwrap().total as u32
+  }
+
+  #[allow(dead_code)]
+  fn render(&self, path: &Path) -> String {
+    let (w, h) = (self.width() as usize, self.height() as usize);
+    path.render(w, h, |(x, y)| (b'0' + self.data[y][x]) as char)
   }
 }
 
@@ -97,6 +42,8 @@ pub fn run(content: &str) {
 
 #[cfg(test)]
 mod tests {
+  use crate::crucible::{self, Rules, Turn};
+
   const TEST: &str = "\
 2413432311323
 3215453535623
@@ -123,4 +70,70 @@ mod tests {
     let test = super::HeatMap::parse(TEST);
     assert_eq!(test.travel(10, 4), 94);
   }
+
+  #[test]
+  fn path() {
+    let test = super::HeatMap::parse(TEST);
+    let path = test.path(&Rules::crucible(4, 10)).unwrap();
+    assert_eq!(path.total, 94);
+    assert_eq!(path.steps.iter().map(|s| s.cost).sum::<u64>(), 94);
+    assert_eq!(path.steps.last().map(|s| s.pos), Some((12, 12)));
+    // Runs between turns are four to ten steps long.
+    let mut runs = vec![1];
+    for w in path.steps.windows(2) {
+      if w[0].dir == w[1].dir { *runs.last_mut().unwrap() += 1; } else { runs.push(1); }
+    }
+    assert!(runs.iter().all(|r| (4..=10).contains(r)), "{:?}", runs);
+    let picture = test.render(&path);
+    assert_eq!(picture.lines().count(), 13);
+    assert_eq!(picture.chars().filter(|c| "<>^v".contains(*c)).count(), path.steps.len());
+    assert!(path.svg(13, 13, |_| Some(1)).contains("<polyline points=\"5,5 15,5 "));
+
+    // Each turn adds ten: the best path has as few turns as possible.
+    let rules = Rules { turn_cost: 10, ..Rules::crucible(1, 3) };
+    let path = test.path(&rules).unwrap();
+    let turns = path.steps.windows(2).filter(|w| w[0].dir != w[1].dir).count() as u64;
+    let heat = path.steps.iter().map(|s| test.data[s.pos.1][s.pos.0] as u64).sum::<u64>();
+    assert_eq!(path.total, heat + 10 * turns);
+    assert!(heat >= 102);
+    // Only right turns.
+    let rules = Rules { turns: vec![Turn::Right], ..Rules::crucible(1, 3) };
+    let path = test.path(&rules).unwrap();
+    assert!(path.steps.windows(2).all(|w| matches!(w[0].dir.turn(w[1].dir), Turn::Straight | Turn::Right)));
+    assert!(path.total >= 102);
+  }
+
+  #[test]
+  fn heuristic() {
+    let test = super::HeatMap::parse(TEST);
+    for rules in [Rules::crucible(1, 3), Rules::crucible(4, 10), Rules::free()] {
+      let plain = test.path(&rules).unwrap();
+      let guided = test.path(&Rules { heuristic: true, ..rules }).unwrap();
+      assert_eq!(plain.total, guided.total);
+    }
+  }
+
+  // The chiton cave of 2021 day 15: free movement, tiled five times with
+  // risks that wrap from 9 to 1.
+  #[test]
+  fn chitons() {
+    let cave = super::HeatMap::parse("\
+1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581");
+    assert_eq!(cave.path(&Rules::free()).unwrap().total, 40);
+    let risk = |(x, y): crucible::Point| {
+      let raw = cave.data[y % 10][x % 10] as u64 + (x / 10 + y / 10) as u64;
+      Some((raw - 1) % 9 + 1)
+    };
+    let path = crucible::shortest_path(50, 50, risk, (0, 0), (49, 49), &Rules::free());
+    assert_eq!(path.map(|p| p.total), Some(315));
+  }
 }
@@ -1,148 +1,11 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-
-const START: &str = "broadcaster";
-
-#[derive(Clone, Copy, Debug)]
-enum Pulse {
-  Low,
-  High,
-}
-
-#[derive(Clone, Debug)]
-enum Module {
-  Broadcaster,
-  FlipFlop(Pulse),
-  Conjunction(HashMap<String, Pulse>),
-}
-
-#[derive(Clone, Debug)]
-struct Node {
-  name: String,
-  module: Module,
-  target: Vec<String>,
-}
-
-#[derive(Clone, Debug)]
-struct Relay {
-  nodes: HashMap<String, Node>,
-}
-
-impl Pulse {
-  fn inverse(&self) -> Pulse {
-    match self {
-      Pulse::Low => Pulse::High,
-      Pulse::High => Pulse::Low,
-    }
-  }
-}
-
-impl Module {
-  fn process(&mut self, signal: Pulse, from: &str) -> Option<Pulse> {
-    match self {
-      Module::Broadcaster => Some(signal),
-      Module::FlipFlop(state) => {
-        match signal {
-          Pulse::High => None,
-          Pulse::Low => {
-            *state = state.inverse();
-            Some(*state)
-          },
-        }
-      },
-      Module::Conjunction(cmap) => {
-        *cmap.get_mut(from).unwrap() = signal;
-        let all_high = cmap.values().all(|v| matches!(v, Pulse::High));
-        Some(if all_high {Pulse::Low} else {Pulse::High})
-      },
-    }
-  }
-}
-
-impl Node {
-  fn parse(text: &str) -> Node {
-    let (s1, s2) = text.split_once(" -> ").unwrap();
-    let target = s2.split(", ").map(String::from).collect::<Vec<_>>();
-    let (name, module) = match s1.chars().nth(0).unwrap() {
-      '%' => (&s1[1..], Module::FlipFlop(Pulse::Low)),
-      '&' => (&s1[1..], Module::Conjunction(HashMap::new())),
-      _ => (s1, Module::Broadcaster),
-    };
-    Node { name: String::from(name), module, target }
-  }
-}
-
-impl Relay {
-  fn parse(text: &str) -> Relay {
-    let mut nodes = text.lines().map(Node::parse)
-      .map(|x| (x.name.clone(), x)).collect::<HashMap<_,_>>();
-    let mut rmap = HashMap::<String, Vec<String>>::new();
-    for node in nodes.values() {
-      for t in &node.target {
-        rmap.entry(t.clone()).or_default().push(node.name.clone());
-      }
-    }
-    for (k, v) in rmap.into_iter() {
-      if let Some(x) = nodes.get_mut(&k) {
-        if let Module::Conjunction(cmap) = &mut x.module {
-          cmap.extend(v.into_iter().map(|s| (s, Pulse::Low)));
-        }
-      }
-    }
-    assert!(nodes.contains_key(START));
-    Relay { nodes }
-  }
-
-  fn press(&mut self, start: &str) -> (usize, usize) {
-    let init = (String::new(), String::from(start), Pulse::Low);
-    let mut queue = VecDeque::from([init]);
-    let (mut low, mut high) = (0_usize, 0_usize);
-    while let Some((from, to, signal)) = queue.pop_front() {
-      match signal {
-        Pulse::Low => low += 1,
-        Pulse::High => high += 1,
-      };
-      if let Some(node) = self.nodes.get_mut(&to) {
-        if let Some(next) = node.module.process(signal, &from) {
-          queue.extend(node.target.iter()
-            .map(|v| (to.clone(), v.clone(), next)));
-        }
-      }
-    }
-    (low, high)
-  }
-
-  fn repeat(&mut self, count: usize) -> usize {
-    let (low, high) = (0..count).fold((0_usize, 0_usize), |acc, _| {
-      let cur = self.press(START);
-      (acc.0 + cur.0, acc.1 + cur.1)
-    });
-    low * high
-  }
-
-  fn state(&self) -> String {
-    self.nodes.values().filter_map(|x| match x.module {
-      Module::FlipFlop(v) => Some(if matches!(v, Pulse::High) {'1'} else {'0'}),
-      _ => None,
-    }).collect()
-  }
-
-  fn count_disjoint(&self) -> usize {
-    let init = self.nodes.get(START).unwrap();
-    init.target.iter().map(|start| {
-      let mut partial = self.clone();
-      let mut visited = HashSet::<String>::new();
-      while visited.insert(partial.state()) {
-        partial.press(start);
-      }
-      visited.len()
-    }).product()
-  }
-}
+use crate::circuit::Relay;
 
 pub fn run(content: &str) {
   let relay = Relay::parse(content);
   let res1 = relay.clone().repeat(1000);
-  let res2 = relay.count_disjoint();
+  let analysis = relay.analyze("rx").unwrap();
+  let res2 = analysis.first_press()
+    .unwrap_or_else(|| panic!("Counters never align:\n{}", analysis));
   println!("{} {}", res1, res2);
 }
 
@@ -168,4 +31,51 @@ broadcaster -> a
     assert_eq!(t1.repeat(1000), 32000000);
     assert_eq!(t2.repeat(1000), 11687500);
   }
+
+  const TEST_3: &str = "\
+broadcaster -> a, b
+%a -> na
+&na -> z
+%b -> c
+%c -> z
+&z -> rx";
+
+  #[test]
+  fn counters() {
+    let relay = super::Relay::parse(TEST_3);
+    let analysis = relay.analyze("rx").unwrap();
+    assert_eq!(analysis.collector, "z");
+    let periods = analysis.counters.iter()
+      .map(|c| (c.output.as_str(), c.phase, c.period)).collect::<Vec<_>>();
+    assert_eq!(periods, [("c", 0, 4), ("na", 0, 2)]);
+    assert_eq!(analysis.first_press(), Some(2));
+    assert_eq!(analysis.to_string(), "\
+rx <- z
+  c (2 nodes): phase 0, period 4, hits [2]
+  na (2 nodes): phase 0, period 2, hits [2]
+");
+
+    let mut brute = relay.clone();
+    let first = (1..).find(|_| {
+      let mut low = false;
+      brute.press_with(crate::circuit::START, |_, to, signal| {
+        low |= to == "rx" && signal == crate::circuit::Pulse::Low;
+      });
+      low
+    });
+    assert_eq!(first, Some(2));
+
+    let conflict = TEST_3.replace("%a -> na\n&na -> z", "%a -> z");
+    let analysis = super::Relay::parse(&conflict).analyze("rx").unwrap();
+    assert_eq!(analysis.first_press(), None);
+    assert!(super::Relay::parse(TEST_2).analyze("output").is_err());
+  }
+
+  #[test]
+  fn dot() {
+    let dot = super::Relay::parse(TEST_1).to_dot();
+    assert!(dot.starts_with("digraph circuit {"));
+    assert!(dot.contains("  inv [shape=ellipse];"));
+    assert!(dot.contains("  broadcaster -> a;"));
+  }
 }
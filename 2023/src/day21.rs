@@ -1,5 +1,6 @@
 use std::cmp;
 use std::collections::{HashMap, HashSet};
+use std::env;
 
 type Point = (i32, i32);
 
@@ -93,6 +94,14 @@ impl Field {
     if steps % 2 == limit % 2 {s1.len()} else {s2.len()}
   }
 
+  // Whether the field suits `compute`: square, of odd size, with the start
+  // in the middle and nothing blocking its row or column.
+  fn is_centered(&self) -> bool {
+    let n = self.size.0;
+    n % 2 == 1 && self.size.1 == n && self.start == (n / 2, n / 2) &&
+      !self.walls.iter().any(|&(x, y)| x == n / 2 || y == n / 2)
+  }
+
   fn compute(&self, count: i32) -> usize {
     let n = self.size.0;
     assert_eq!(n % 2, 1);
@@ -126,11 +135,98 @@ impl Field {
   }
 }
 
+// Breadth-first search over the infinitely tiled field, remembering how
+// many cells are first reached at each distance from the start.
+struct Frontier<'a> {
+  field: &'a Field,
+  seen: HashSet<Point>,
+  cur: Vec<Point>,
+  layers: Vec<usize>,
+}
+
+impl<'a> Frontier<'a> {
+  fn new(field: &'a Field) -> Frontier<'a> {
+    let seen = HashSet::from([field.start]);
+    Frontier { field, seen, cur: vec![field.start], layers: vec![1] }
+  }
+
+  fn advance(&mut self, steps: usize) {
+    let size = self.field.size;
+    for _ in 0..steps {
+      let mut next = Vec::<Point>::new();
+      for &(x, y) in &self.cur {
+        for p in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+          let wp = (p.0.rem_euclid(size.0), p.1.rem_euclid(size.1));
+          if !self.field.walls.contains(&wp) && self.seen.insert(p) { next.push(p); }
+        }
+      }
+      self.layers.push(next.len());
+      self.cur = next;
+    }
+  }
+
+  // Cells reachable in exactly `steps` (which must be already explored).
+  fn reachable(&self, steps: usize) -> usize {
+    self.layers[..=steps].iter().skip(steps % 2).step_by(2).sum()
+  }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+  if b == 0 {a} else {gcd(b, a % b)}
+}
+
+impl Field {
+  // Makes no assumption about the field: samples the reachable count once
+  // per tiling period until its second difference settles, then continues
+  // the quadratic. Cells reachable in an exact number of steps alternate
+  // with its parity, so an odd period is doubled to keep the parity fixed.
+  fn extrapolate(&self, count: usize) -> usize {
+    const STABLE: usize = 3;
+    const LIMIT: usize = 1000;
+    let (w, h) = (self.size.0 as usize, self.size.1 as usize);
+    let tiling = w / gcd(w, h) * h;
+    let period = if tiling % 2 == 1 {tiling * 2} else {tiling};
+    let (rest, target) = (count % period, count / period);
+    let mut frontier = Frontier::new(self);
+    frontier.advance(rest);
+    let mut samples = vec![frontier.reachable(rest) as i64];
+    while samples.len() <= target {
+      frontier.advance(period);
+      samples.push(frontier.reachable(rest + period * (samples.len())) as i64);
+
+      let n = samples.len();
+      let d2 = |i: usize| samples[i] - 2 * samples[i - 1] + samples[i - 2];
+      if n >= STABLE + 2 && (n - STABLE..n).all(|i| d2(i) == d2(n - 1)) {
+        let k = (target - (n - 1)) as i64;
+        let (step, accel) = (samples[n - 1] - samples[n - 2], d2(n - 1));
+        return (samples[n - 1] + k * step + accel * k * (k + 1) / 2) as usize;
+      }
+      assert!(n < LIMIT, "growth does not settle");
+    }
+    samples[target] as usize
+  }
+
+  // Step counts (among the given ones) where extrapolation disagrees with
+  // the brute force search.
+  fn verify(&self, steps: &[usize]) -> Vec<usize> {
+    steps.iter().copied()
+      .filter(|&s| self.extrapolate(s) != self.simple(s)).collect()
+  }
+}
+
 pub fn run(content: &str) {
   let field = Field::parse(content);
   let res1 = field.simple(64);
-  let res2 = field.compute(26501365);
+  let res2 = if field.is_centered() {
+    field.compute(26501365)
+  } else {
+    field.extrapolate(26501365)
+  };
   println!("{} {}", res1, res2);
+  if env::args().any(|a| a == "--verify") {
+    let failed = field.verify(&[64, 100, 200, 500]);
+    assert!(failed.is_empty(), "extrapolation disagrees at {:?}", failed);
+  }
 }
 
 #[cfg(test)]
@@ -174,4 +270,21 @@ mod tests {
     assert_eq!(clear.simple(99), 7471);
     assert_eq!(clear.compute(99), 7471);
   }
+
+  #[test]
+  pub fn extrapolate() {
+    let test = super::Field::parse(TEST_1);
+    assert_eq!(test.extrapolate(100), 6536);
+    let clear = super::Field::parse(TEST_2);
+    assert!(clear.is_centered() && !test.is_centered());
+    assert_eq!(clear.extrapolate(99), clear.compute(99));
+    assert_eq!(test.extrapolate(500), 167004);
+    assert_eq!(test.extrapolate(1000), 668697);
+    assert_eq!(test.extrapolate(5000), 16733044);
+    let shifted = super::Field::parse(&TEST_1.replace('S', ".").replacen("..#..", "..#S.", 1));
+    assert!(test.verify(&[0, 1, 6, 10, 50, 100, 111, 150]).is_empty());
+    assert!(shifted.verify(&[7, 33, 120, 199]).is_empty());
+    let odd = super::Field::parse(".....\n...#.\n.#S..\n.#...\n.....");
+    assert!(odd.verify(&[2, 25, 40, 77, 150]).is_empty());
+  }
 }
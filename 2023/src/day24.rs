@@ -1,7 +1,8 @@
 use std::ops::RangeInclusive;
+use crate::geometry::{self, Ray};
+use crate::rational::Rational;
 
 type Point = (i64, i64, i64);
-type Fraction = (i128, i128);
 
 #[derive(Debug)]
 struct Particle {
@@ -9,13 +10,6 @@ struct Particle {
   velocity: Point,
 }
 
-#[derive(Debug)]
-struct Line2D {
-  a: i64,
-  b: i64,
-  c: i64,
-}
-
 impl Particle {
   fn parse(text: &str) -> Particle {
     let sub = |s: &str| -> Point {
@@ -28,87 +22,21 @@ impl Particle {
     Particle { position: sub(s1), velocity: sub(s2) }
   }
 
+  fn ray(&self) -> Ray {
+    let vector = |(x, y, z): Point| [x as i128, y as i128, z as i128];
+    Ray { origin: vector(self.position), direction: vector(self.velocity) }
+  }
+
   fn count_2d(&self, other: &[Particle], range: &RangeInclusive<i64>) -> usize {
-    let line = Line2D::new(self);
+    let ray = self.ray();
+    let (low, high) = (Rational::from(*range.start() as i128), Rational::from(*range.end() as i128));
     other.iter().filter(|part| {
-      let (x, y) = line.intersection(&Line2D::new(part));
-      within(&x, range) && within(&y, range) &&
-      !is_past(&x, self.position.0, self.velocity.0) &&
-      !is_past(&x, part.position.0, part.velocity.0)
+      ray.crossing_2d(&part.ray()).is_some_and(|c| {
+        c.point.iter().all(|v| low <= *v && *v <= high) &&
+        c.t.signum() >= 0 && c.s.signum() >= 0
+      })
     }).count()
   }
-
-  fn get_at(&self, t: i64) -> Point {
-    (self.position.0 + t * self.velocity.0,
-     self.position.1 + t * self.velocity.1,
-     self.position.2 + t * self.velocity.2)
-  }
-
-  fn get_pair(&self, i: usize) -> (i64, i64) {
-    match i {
-      0 => (self.position.0, self.velocity.0),
-      1 => (self.position.1, self.velocity.1),
-      2 => (self.position.2, self.velocity.2),
-      _ => panic!(),
-    }
-  }
-}
-
-impl Line2D {
-  fn new(part: &Particle) -> Line2D {
-    let (x1, y1) = (part.position.0, part.position.1);
-    let (x2, y2) = (x1 + part.velocity.0, y1 + part.velocity.1);
-    let a = y2 - y1;
-    let b = x1 - x2;
-    let c = -b * y1 - a * x1;
-    Line2D { a, b, c }
-  }
-
-  fn intersection(&self, other: &Line2D) -> (Fraction, Fraction) {
-    let (a1, b1, c1) = (self.a as i128, self.b as i128, self.c as i128);
-    let (a2, b2, c2) = (other.a as i128, other.b as i128, other.c as i128);
-    let d = a1 * b2 - a2 * b1;
-    let norm = |v: i128| { if d >= 0 {(v, d)} else {(-v, -d)} };
-    (norm(b1 * c2 - b2 * c1), norm(a2 * c1 - a1 * c2))
-  }
-}
-
-fn within(frac: &Fraction, range: &RangeInclusive<i64>) -> bool {
-  frac.0 >= *range.start() as i128 * frac.1 &&
-  frac.0 <= *range.end() as i128 * frac.1
-}
-
-fn is_past(frac: &Fraction, pos: i64, inc: i64) -> bool {
-  let (a, b) = (frac.0, pos as i128 * frac.1);
-  if a != b {(a > b) == (inc < 0)} else {false}
-}
-
-fn solve(data: &[Particle], (u0, v0, _): Point) -> Option<Point> {
-  let ((x1, u1), (y1, v1)) = (data[0].get_pair(0), data[0].get_pair(1));
-  let ((x2, u2), (y2, v2)) = (data[1].get_pair(0), data[1].get_pair(1));
-  let a = (y2 - y1) * (u2 - u0) + (x1 - x2) * (v2 - v0);
-  let b = (v1 - v0) * (u2 - u0) - (u1 - u0) * (v2 - v0);
-  if b == 0 { return None; }
-  let t1 = a / b;
-  let (sx, sy) = (x1 + t1 * (u1 - u0), y1 + t1 * (v1 - v0));
-  let mut z: Vec<(i64, i64)> = vec![];
-  if !data.iter().all(|part| {
-    let (x, u) = part.get_pair(0);
-    if u == u0 { return true; }
-    let t = (x1 - x + t1 * (u1 - u0)) / (u - u0);
-    let p = part.get_at(t);
-    z.push((t, p.2));
-    p.0 == sx + t * u0 && p.1 == sy + t * v0
-  }) { return None; }
-  let ((t1, z1), (t2, z2)) = (z[0], z[1]);
-  let w0 = (z2 - z1) / (t2 - t1);
-  let sz = z1 - t1 * w0;
-  Some((sx, sy, sz))
-}
-
-fn solve_some(data: &[Particle], range: &RangeInclusive<i64>) -> Option<Point> {
-  range.clone().flat_map(|u| range.clone().filter_map(move |v|
-    solve(data, (u, v, 0)))).next()
 }
 
 pub fn run(content: &str) {
@@ -116,8 +44,9 @@ pub fn run(content: &str) {
   let range = 200_000_000_000_000..=400_000_000_000_000_i64;
   let res1 = (0..data.len()).map(|i| data[i].count_2d(&data[i+1..], &range))
     .sum::<usize>();
-  let init = solve_some(&data, &(-1000..=1000)).unwrap();
-  let res2 = init.0 + init.1 + init.2;
+  let rays = data.iter().map(|p| p.ray()).collect::<Vec<_>>();
+  let rock = geometry::throw(&rays).expect("no rock hits every hailstone");
+  let res2 = rock.origin.iter().sum::<i128>();
   println!("{} {}", res1, res2);
 }
 
@@ -136,4 +65,39 @@ mod tests {
     let it = (0..a.len()).map(|i| a[i].count_2d(&a[i+1..], &(7..=27)));
     assert_eq!(it.collect::<Vec<_>>(), [2, 0, 0, 0, 0]);
   }
+
+  #[test]
+  fn rational() {
+    use super::Rational;
+    let (a, b) = (Rational::new(6, -4), Rational::new(1, 3));
+    assert_eq!(a, Rational::new(-3, 2));
+    assert_eq!((a + b).to_string(), "-7/6");
+    assert_eq!((a - b).to_string(), "-11/6");
+    assert_eq!((a * b).to_string(), "-1/2");
+    assert_eq!((a / b).to_string(), "-9/2");
+    assert!(a < b && b < Rational::from(1));
+    assert_eq!((b * Rational::from(3)).to_integer(), Some(1));
+    // Overflow panics instead of wrapping.
+    let huge = Rational::from(i128::MAX);
+    assert!(std::panic::catch_unwind(|| huge - Rational::from(-1)).is_err());
+    let ray = super::Ray { origin: [i128::MAX; 3], direction: [1; 3] };
+    assert!(std::panic::catch_unwind(|| ray.at(1)).is_err());
+    assert!(std::panic::catch_unwind(|| super::geometry::cross(&[i128::MAX, 1, 0], &[-1, 1, 0])).is_err());
+  }
+
+  #[test]
+  fn large() {
+    let a = TEST.lines().map(|s| super::Particle::parse(s).ray()).collect::<Vec<_>>();
+    let c = a[0].crossing_2d(&a[1]).unwrap();
+    assert_eq!(c.point.map(|v| v.to_string()), ["43/3", "46/3"]);
+    assert!(a[1].crossing_2d(&a[2]).is_none());
+    assert!(a[0].is_skew(&a[1]));
+
+    let rock = super::geometry::throw(&a).unwrap();
+    assert_eq!(rock.origin, [24, 13, 10]);
+    assert_eq!(rock.direction, [-3, 1, 2]);
+    let times = a.iter().map(|r| rock.collision(r).unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(times, ["5", "3", "4", "6", "1"]);
+    assert!(super::geometry::throw(&a[..3]).is_some());
+  }
 }
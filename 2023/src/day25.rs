@@ -1,87 +1,15 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-
-#[derive(Debug)]
-struct Graph {
-  names: Vec<String>,
-  conn: Vec<Vec<usize>>,
-}
-
-impl Graph {
-  fn parse(text: &str) -> Graph {
-    let mut names: Vec<String> = vec![];
-    let mut conn: Vec<Vec<usize>> = vec![];
-    let mut rmap = HashMap::<String, usize>::new();
-    let mut index = |s: &str| -> usize {
-      *rmap.entry(String::from(s)).or_insert_with(|| {
-        names.push(String::from(s));
-        names.len() - 1
-      })
-    };
-    for line in text.lines() {
-      let (s1, s2) = line.split_once(": ").unwrap();
-      let i = index(s1);
-      if i == conn.len() { conn.push(vec![]); }
-      for to in s2.split(' ') {
-        let j = index(to);
-        if j == conn.len() { conn.push(vec![]); }
-        conn[i].push(j);
-        conn[j].push(i);
-      }
-    }
-    Graph { names, conn }
-  }
-
-  fn find_distinct(&self, from: usize, to: usize, count: usize) -> bool {
-    let direct = self.conn[from].contains(&to);
-    let mut in_use = HashSet::from([from]);
-    'main: for _ in 0..(count - direct as usize) {
-      let mut queue = VecDeque::from([vec![from]]);
-      let mut visited = in_use.clone();
-      while let Some(path) = queue.pop_front() {
-        let last = *path.last().unwrap();
-        for cur in &self.conn[last] {
-          if *cur == to {
-            if path.len() == 1 { continue; }
-            in_use.extend(path.into_iter());
-            continue 'main;
-          }
-          if !visited.contains(cur) {
-            let mut new_path = path.clone();
-            new_path.push(*cur);
-            queue.push_back(new_path);
-            visited.insert(*cur);
-          }
-        }
-      }
-      return false;
-    }
-    true
-  }
-
-  fn find_cliques(&self, count: usize) -> Vec<HashSet<usize>> {
-    let mut result: Vec<HashSet<usize>> = vec![];
-    let mut visited = HashSet::<usize>::new();
-    for i in 0..self.names.len() {
-      if visited.contains(&i) { continue; }
-      let mut connect: Vec<usize> = vec![i];
-      for j in i+1..self.names.len() {
-        if visited.contains(&j) { continue; }
-        if self.find_distinct(i, j, count) { connect.push(j); }
-      }
-      if connect.len() > 1 {
-        visited.extend(connect.iter().cloned());
-        result.push(HashSet::from_iter(connect.into_iter()));
-      }
-    }
-    result
-  }
-}
+use crate::cut::Graph;
 
 pub fn run(content: &str) {
   let graph = Graph::parse(content);
-  let clique = graph.find_cliques(4);
-  let res1 = clique.iter().map(|x| x.len()).product::<usize>();
-  println!("{}", res1);
+  let cut = graph.min_cut().unwrap();
+  // Across the cut no pair has more edge-disjoint paths than the cut has wires.
+  if let Some(&(u, v)) = cut.edges.first() {
+    let local = graph.st_cut(u, v);
+    assert_eq!(local.edges.len(), cut.edges.len(), "flow disagrees with the cut");
+  }
+  let (a, b) = cut.sizes();
+  println!("{}", a * b);
 }
 
 #[cfg(test)]
@@ -108,4 +36,41 @@ frs: qnr lhk lsr";
       .map(|x| x.len()).collect::<Vec<_>>();
     assert_eq!(size, [6, 9]);
   }
+
+  #[test]
+  fn cut() {
+    let test = super::Graph::parse(TEST);
+    let name = |i: usize| test.names[i].as_str();
+    let global = test.min_cut().unwrap();
+    let mut wires = global.edges.iter().map(|&(i, j)| {
+      let mut w = [name(i), name(j)];
+      w.sort();
+      w
+    }).collect::<Vec<_>>();
+    wires.sort();
+    assert_eq!(wires, [["bvb", "cmg"], ["hfx", "pzl"], ["jqt", "nvd"]]);
+    let (a, b) = global.sizes();
+    assert_eq!(a * b, 54);
+
+    let index = |s: &str| test.names.iter().position(|n| n == s).unwrap();
+    let local = test.st_cut(index("jqt"), index("cmg"));
+    assert_eq!(local.edges.len(), 3);
+    assert!(local.side[index("xhk")] && !local.side[index("lsr")]);
+    assert_eq!(test.connectivity(index("hfx"), index("xhk"), 10), 5);
+    assert_eq!(test.connectivity(index("hfx"), index("xhk"), 2), 2);
+    let leaf = test.st_cut(index("qnr"), index("jqt"));
+    assert_eq!(leaf.sizes(), (9, 6));
+  }
+
+  #[test]
+  fn parallel() {
+    // a and b are listed as connected twice, c hangs off b.
+    let test = super::Graph::parse("a: b\nb: a c");
+    assert_eq!(test.connectivity(0, 1, 10), 2);
+    assert_eq!(test.connectivity(0, 2, 10), 1);
+    assert_eq!(test.st_cut(0, 1).edges.len(), 2);
+    assert_eq!(test.min_cut().unwrap().edges, [(1, 2)]);
+    assert_eq!(test.connectivity(1, 1, 10), 0);
+    assert_eq!(test.st_cut(2, 2).sizes(), (1, 2));
+  }
 }
@@ -0,0 +1,141 @@
+use crate::rational::Rational;
+
+pub type Vector = [i128; 3];
+
+// Points origin + t * direction; for hailstones t is the time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+  pub origin: Vector,
+  pub direction: Vector,
+}
+
+// Crossing of two rays in the xy plane, with the parameter of each ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crossing {
+  pub point: [Rational; 2],
+  pub t: Rational,
+  pub s: Rational,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+  if b == 0 {a.abs()} else {gcd(b, a % b)}
+}
+
+fn checked<T>(value: Option<T>) -> T {
+  value.expect("arithmetic overflow")
+}
+
+fn plus(a: i128, b: i128) -> i128 {
+  checked(a.checked_add(b))
+}
+
+fn minus(a: i128, b: i128) -> i128 {
+  checked(a.checked_sub(b))
+}
+
+fn times(a: i128, b: i128) -> i128 {
+  checked(a.checked_mul(b))
+}
+
+pub fn add(a: &Vector, b: &Vector) -> Vector {
+  [0, 1, 2].map(|i| plus(a[i], b[i]))
+}
+
+pub fn sub(a: &Vector, b: &Vector) -> Vector {
+  [0, 1, 2].map(|i| minus(a[i], b[i]))
+}
+
+pub fn dot(a: &Vector, b: &Vector) -> i128 {
+  (0..3).map(|i| times(a[i], b[i])).fold(0, plus)
+}
+
+pub fn cross(a: &Vector, b: &Vector) -> Vector {
+  let m = |i: usize, j: usize| minus(times(a[i], b[j]), times(a[j], b[i]));
+  [m(1, 2), m(2, 0), m(0, 1)]
+}
+
+// Shortest integer vector with the same direction.
+pub fn primitive(a: &Vector) -> Vector {
+  let g = a.iter().fold(0, |acc, &v| gcd(acc, v)).max(1);
+  [a[0] / g, a[1] / g, a[2] / g]
+}
+
+impl Ray {
+  pub fn at(&self, t: i128) -> Vector {
+    [0, 1, 2].map(|i| plus(self.origin[i], times(t, self.direction[i])))
+  }
+
+  // Intersection of the projections on the xy plane, None if parallel.
+  pub fn crossing_2d(&self, other: &Ray) -> Option<Crossing> {
+    let (d1, d2) = (&self.direction, &other.direction);
+    let det = cross(d1, d2)[2];
+    if det == 0 { return None; }
+    let o = sub(&other.origin, &self.origin);
+    let t = Rational::new(cross(&o, d2)[2], det);
+    let s = Rational::new(cross(&o, d1)[2], det);
+    let point = [0, 1].map(|i| Rational::from(self.origin[i]) + t * Rational::from(d1[i]));
+    Some(Crossing { point, t, s })
+  }
+
+  // Lines that are neither parallel nor intersecting.
+  #[allow(dead_code)]
+  pub fn is_skew(&self, other: &Ray) -> bool {
+    let normal = cross(&self.direction, &other.direction);
+    normal != [0; 3] && dot(&sub(&other.origin, &self.origin), &normal) != 0
+  }
+
+  // Time at which the two rays are at the same point, if they ever are.
+  pub fn collision(&self, other: &Ray) -> Option<Rational> {
+    let p = sub(&other.origin, &self.origin);
+    let v = sub(&self.direction, &other.direction);
+    if cross(&p, &v) != [0; 3] { return None; }
+    match (0..3).find(|&i| v[i] != 0) {
+      Some(i) => Some(Rational::new(p[i], v[i])),
+      None if p == [0; 3] => Some(Rational::from(0)),
+      None => None,
+    }
+  }
+}
+
+// Integer ray that collides with every given ray at a non-negative time.
+// In the frame of the first ray it passes through the origin, so it lies
+// in the plane spanned by the origin and each other ray; two such planes
+// give its direction.
+pub fn throw(rays: &[Ray]) -> Option<Ray> {
+  let base = rays.first()?;
+  let local = rays[1..].iter().map(|r| Ray {
+    origin: sub(&r.origin, &base.origin),
+    direction: sub(&r.direction, &base.direction),
+  }).collect::<Vec<_>>();
+  let normals = local.iter().map(|r| cross(&r.origin, &r.direction)).collect::<Vec<_>>();
+  let (i, j, dir) = (0..local.len()).flat_map(|i| (i + 1..local.len()).map(move |j| (i, j)))
+    .map(|(i, j)| (i, j, primitive(&cross(&normals[i], &normals[j]))))
+    .find(|(_, _, d)| *d != [0; 3])?;
+
+  // Time at which the line through the origin along `dir` meets a ray.
+  let meet = |r: &Ray| -> Option<Rational> {
+    let (a, b) = (cross(&r.origin, &dir), cross(&r.direction, &dir));
+    let k = (0..3).find(|&k| b[k] != 0)?;
+    Some(-Rational::new(a[k], b[k]))
+  };
+  let (t1, t2) = (meet(&local[i])?, meet(&local[j])?);
+  if t1 == t2 { return None; }
+  let (p1, p2) = (local[i].at(t1.to_integer()?), local[j].at(t2.to_integer()?));
+  let dt = (t1 - t2).to_integer()?;
+  let mut direction = [0; 3];
+  let mut origin = [0; 3];
+  let offset = sub(&p1, &p2);
+  for k in 0..3 {
+    if offset[k] % dt != 0 { return None; }
+    direction[k] = offset[k] / dt;
+    origin[k] = minus(p1[k], times(t1.to_integer()?, direction[k]));
+  }
+  let rock = Ray {
+    origin: add(&origin, &base.origin),
+    direction: add(&direction, &base.direction),
+  };
+  let valid = rays.iter().all(|r| {
+    rock.collision(r).is_some_and(|t| t.signum() >= 0)
+  });
+  if valid {Some(rock)} else {None}
+}
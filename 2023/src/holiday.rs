@@ -0,0 +1,101 @@
+use std::fmt;
+
+pub const BOXES: usize = 256;
+
+// Holiday ASCII String Helper: the box of a key.
+pub fn hash<K: AsRef<[u8]> + ?Sized>(key: &K) -> u8 {
+  key.as_ref().iter().fold(0_u8, |a, &b| a.wrapping_add(b).wrapping_mul(17))
+}
+
+// Map with HASH as its hash function and 256 boxes; each box keeps its
+// entries in insertion order, and replacing a value keeps its slot.
+#[derive(Clone, Debug)]
+pub struct HolidayMap<K, V> {
+  boxes: Vec<Vec<(K, V)>>,
+  len: usize,
+}
+
+impl<K: AsRef<[u8]>, V> HolidayMap<K, V> {
+  pub fn new() -> HolidayMap<K, V> {
+    HolidayMap { boxes: (0..BOXES).map(|_| vec![]).collect(), len: 0 }
+  }
+
+  fn slot<Q: AsRef<[u8]> + ?Sized>(&self, key: &Q) -> (usize, Option<usize>) {
+    let index = hash(key) as usize;
+    let pos = self.boxes[index].iter().position(|(k, _)| k.as_ref() == key.as_ref());
+    (index, pos)
+  }
+
+  // Returns the previous value if the key was present.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    match self.slot(&key) {
+      (index, Some(pos)) => Some(std::mem::replace(&mut self.boxes[index][pos].1, value)),
+      (index, None) => {
+        self.boxes[index].push((key, value));
+        self.len += 1;
+        None
+      },
+    }
+  }
+
+  pub fn remove<Q: AsRef<[u8]> + ?Sized>(&mut self, key: &Q) -> Option<V> {
+    let (index, pos) = self.slot(key);
+    self.len -= pos.is_some() as usize;
+    pos.map(|pos| self.boxes[index].remove(pos).1)
+  }
+
+  // Non-empty boxes with their numbers.
+  pub fn boxes(&self) -> impl Iterator<Item = (usize, &[(K, V)])> {
+    self.boxes.iter().enumerate().filter(|(_, b)| !b.is_empty()).map(|(i, b)| (i, &b[..]))
+  }
+}
+
+#[allow(dead_code)]
+impl<K: AsRef<[u8]>, V> HolidayMap<K, V> {
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn get<Q: AsRef<[u8]> + ?Sized>(&self, key: &Q) -> Option<&V> {
+    let (index, pos) = self.slot(key);
+    pos.map(|pos| &self.boxes[index][pos].1)
+  }
+
+  pub fn get_mut<Q: AsRef<[u8]> + ?Sized>(&mut self, key: &Q) -> Option<&mut V> {
+    let (index, pos) = self.slot(key);
+    pos.map(|pos| &mut self.boxes[index][pos].1)
+  }
+
+  pub fn contains_key<Q: AsRef<[u8]> + ?Sized>(&self, key: &Q) -> bool {
+    self.slot(key).1.is_some()
+  }
+
+  // Entries box by box, each box in insertion order.
+  pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+    self.boxes.iter().flatten().map(|(k, v)| (k, v))
+  }
+}
+
+impl<K: AsRef<[u8]>, V> Default for HolidayMap<K, V> {
+  fn default() -> HolidayMap<K, V> {
+    HolidayMap::new()
+  }
+}
+
+// Non-empty boxes in the format of the puzzle statement.
+impl<K: AsRef<[u8]> + fmt::Display, V: fmt::Display> fmt::Display for HolidayMap<K, V> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, entries) in self.boxes() {
+      write!(f, "Box {}:", i)?;
+      for (k, v) in entries {
+        write!(f, " [{} {}]", k, v)?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
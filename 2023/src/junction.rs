@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+pub type Point = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dir {
+  Right,
+  Down,
+  Left,
+  Up,
+}
+
+const DIRS: [Dir; 4] = [Dir::Right, Dir::Down, Dir::Left, Dir::Up];
+
+// Weighted graph of the junctions of a grid maze: the start, the end and
+// every cell with more than two open neighbours. Edges are the corridors
+// between them, weighted by their length in steps.
+#[derive(Clone, Debug)]
+pub struct Graph {
+  pub nodes: Vec<Point>,
+  pub edges: Vec<Vec<(usize, usize)>>,
+}
+
+// Partial path: current node, visited nodes, length so far, and the sum of
+// the best incoming edge of every node not yet visited.
+#[derive(Clone, Copy, Debug)]
+struct Branch {
+  node: usize,
+  visited: u128,
+  length: usize,
+  remaining: usize,
+}
+
+impl Dir {
+  fn step(&self, (x, y): Point, grid: &[Vec<u8>]) -> Option<Point> {
+    let (x, y) = match self {
+      Dir::Right => (x + 1, y),
+      Dir::Down => (x, y + 1),
+      Dir::Left => (x.checked_sub(1)?, y),
+      Dir::Up => (x, y.checked_sub(1)?),
+    };
+    if y < grid.len() && x < grid[y].len() {Some((x, y))} else {None}
+  }
+}
+
+impl Graph {
+  // `allowed(symbol, dir)` tells whether a move in the given direction may
+  // leave or enter a cell with that symbol.
+  pub fn compress<F>(grid: &[Vec<u8>], start: Point, end: Point, allowed: F) -> Graph
+  where F: Fn(u8, Dir) -> bool {
+    let at = |(x, y): Point| grid[y][x];
+    let moves = |p: Point| DIRS.iter().filter_map(|&d| {
+      let q = d.step(p, grid)?;
+      if allowed(at(p), d) && allowed(at(q), d) {Some((d, q))} else {None}
+    }).collect::<Vec<_>>();
+    let open = |p: Point| DIRS.iter().filter(|&&d| {
+      d.step(p, grid).is_some_and(|q| DIRS.iter().any(|&e| allowed(at(q), e)))
+    }).count();
+
+    let mut nodes = vec![start, end];
+    for (y, row) in grid.iter().enumerate() {
+      for x in 0..row.len() {
+        let p = (x, y);
+        if p != start && p != end && DIRS.iter().any(|&d| allowed(at(p), d)) && open(p) > 2 {
+          nodes.push(p);
+        }
+      }
+    }
+
+    let edges = nodes.iter().map(|&from| {
+      moves(from).into_iter().filter_map(|(_, first)| {
+        let (mut prev, mut cur, mut length) = (from, first, 1);
+        loop {
+          if let Some(idx) = nodes.iter().position(|&n| n == cur) {
+            return Some((idx, length));
+          }
+          let (_, next) = moves(cur).into_iter().find(|&(_, q)| q != prev)?;
+          (prev, cur, length) = (cur, next, length + 1);
+        }
+      }).collect::<Vec<_>>()
+    }).collect::<Vec<_>>();
+    Graph { nodes, edges }
+  }
+
+  // Length of the longest simple path, searched on all available cores.
+  pub fn longest_path(&self, from: usize, to: usize) -> Option<usize> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    self.longest_path_with(from, to, threads)
+  }
+
+  pub fn longest_path_with(&self, from: usize, to: usize, threads: usize) -> Option<usize> {
+    assert!(self.nodes.len() <= 128, "too many junctions: {}", self.nodes.len());
+    let mut best_in = vec![0; self.nodes.len()];
+    for targets in &self.edges {
+      for &(t, w) in targets { best_in[t] = best_in[t].max(w); }
+    }
+    // If a single node leads to the end, any path must leave it that way.
+    let feeders = (0..self.nodes.len()).filter(|&i| {
+      self.edges[i].iter().any(|&(t, _)| t == to)
+    }).collect::<Vec<_>>();
+    let last = if feeders.len() == 1 {Some(feeders[0])} else {None};
+
+    let root = Branch {
+      node: from,
+      visited: 1 << from,
+      length: 0,
+      remaining: best_in.iter().sum::<usize>() - best_in[from],
+    };
+    // Expand the first levels breadth-first to get enough independent work.
+    let mut branches = vec![root];
+    let mut finished = None;
+    while branches.len() < threads * 8 {
+      let mut next = vec![];
+      for b in &branches {
+        if b.node == to {
+          finished = finished.max(Some(b.length));
+        } else {
+          next.extend(self.children(b, to, last, &best_in));
+        }
+      }
+      branches = next;
+      if branches.is_empty() { break; }
+    }
+
+    let best = AtomicUsize::new(finished.map_or(0, |v| v + 1));
+    let index = AtomicUsize::new(0);
+    thread::scope(|s| {
+      for _ in 0..threads.max(1) {
+        s.spawn(|| {
+          while let Some(b) = branches.get(index.fetch_add(1, Ordering::Relaxed)) {
+            self.search(*b, to, last, &best_in, &best);
+          }
+        });
+      }
+    });
+    best.into_inner().checked_sub(1)
+  }
+
+  fn children<'a>(&'a self, b: &'a Branch, to: usize, last: Option<usize>,
+                  best_in: &'a [usize]) -> impl Iterator<Item = Branch> + 'a {
+    self.edges[b.node].iter().filter(move |&&(t, _)| {
+      b.visited & (1 << t) == 0 && (last != Some(b.node) || t == to)
+    }).map(move |&(t, w)| Branch {
+      node: t,
+      visited: b.visited | (1 << t),
+      length: b.length + w,
+      remaining: b.remaining - best_in[t],
+    })
+  }
+
+  // Depth-first search; `best` holds the longest length found plus one
+  // (zero while no path is known), shared between threads for pruning.
+  fn search(&self, b: Branch, to: usize, last: Option<usize>, best_in: &[usize],
+            best: &AtomicUsize) {
+    if b.node == to {
+      best.fetch_max(b.length + 1, Ordering::Relaxed);
+      return;
+    }
+    if b.length + b.remaining < best.load(Ordering::Relaxed) { return; }
+    for c in self.children(&b, to, last, best_in) {
+      self.search(c, to, last, best_in, best);
+    }
+  }
+}
@@ -26,16 +26,32 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+mod circuit;
+mod workflow;
+mod junction;
+mod cut;
+mod geometry;
+mod piecewise;
+mod nonogram;
+mod holiday;
+#[path = "../../common/crucible.rs"]
+mod crucible;
+#[path = "../../common/polygon.rs"]
+mod polygon;
+#[path = "../../common/rational.rs"]
+mod rational;
+#[cfg(test)]
+mod xorshift;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     assert!(args.len() > 1, "Task number is missing");
 
     let number: usize = args[1].parse().unwrap();
-    let filename: String = if args.len() > 2 {
-        args[2].clone()
-    } else {
-        format!("input/day{:02}.txt", number)
+    // Arguments starting with "--" are options for the day itself.
+    let filename: String = match args[2..].iter().find(|a| !a.starts_with("--")) {
+        Some(name) => name.clone(),
+        None => format!("input/day{:02}.txt", number),
     };
     let content = fs::read_to_string(filename).expect("Error reading input");
 
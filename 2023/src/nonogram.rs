@@ -0,0 +1,253 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cell {
+  Operational,
+  Damaged,
+  Unknown,
+}
+
+// Placements of runs of damaged cells (`groups`, in order, separated by at
+// least one operational cell) on a partially known line. `ways[i][g]`
+// counts the arrangements of cells[i..] with groups[g..] when cell i does
+// not follow a run.
+#[derive(Clone, Debug)]
+pub struct Line {
+  cells: Vec<Cell>,
+  groups: Vec<usize>,
+  operational: Vec<usize>,
+  ways: Vec<Vec<u128>>,
+}
+
+// Arrangements in order, operational before damaged at the first
+// difference; only branches that lead to a full arrangement are taken.
+pub struct Arrangements<'a> {
+  line: &'a Line,
+  stack: Vec<(usize, usize, Vec<Cell>)>,
+}
+
+// Clues for the rows (left to right) and columns (top to bottom).
+#[derive(Clone, Debug)]
+pub struct Nonogram {
+  pub rows: Vec<Vec<usize>>,
+  pub cols: Vec<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+  pub cells: Vec<Vec<Cell>>,
+}
+
+impl Line {
+  pub fn new(cells: &[Cell], groups: &[usize]) -> Line {
+    let (n, m) = (cells.len(), groups.len());
+    let mut operational = vec![0; n + 1];
+    for (i, &c) in cells.iter().enumerate() {
+      operational[i + 1] = operational[i] + (c == Cell::Operational) as usize;
+    }
+    let mut line = Line {
+      cells: cells.to_vec(),
+      groups: groups.to_vec(),
+      operational,
+      ways: vec![vec![0; m + 1]; n + 1],
+    };
+    line.ways[n][m] = 1;
+    for i in (0..n).rev() {
+      for g in 0..=m {
+        line.ways[i][g] = line.skip(i, g) + line.take(i, g).map_or(0, |end| line.ways[end][g + 1]);
+      }
+    }
+    line
+  }
+
+  // Free position after putting group g at cell i and the operational
+  // cell behind it (unless the line ends there), if it fits.
+  fn place(&self, i: usize, g: usize) -> Option<usize> {
+    let end = i + *self.groups.get(g)?;
+    if end > self.cells.len() || self.operational[end] > self.operational[i] { return None; }
+    match self.cells.get(end) {
+      None => Some(end),
+      Some(Cell::Damaged) => None,
+      Some(_) => Some(end + 1),
+    }
+  }
+
+  // Arrangements with cell i operational.
+  fn skip(&self, i: usize, g: usize) -> u128 {
+    if self.cells[i] != Cell::Damaged {self.ways[i + 1][g]} else {0}
+  }
+
+  // Free position after group g put at cell i, if anything can follow.
+  fn take(&self, i: usize, g: usize) -> Option<usize> {
+    self.place(i, g).filter(|&end| self.ways[end][g + 1] > 0)
+  }
+
+  // Cells of group g put at cell i.
+  fn put(&self, result: &mut Vec<Cell>, i: usize, g: usize, end: usize) {
+    let len = self.groups[g];
+    result.extend((0..end - i).map(|k| if k < len {Cell::Damaged} else {Cell::Operational}));
+  }
+
+  pub fn count(&self) -> u128 {
+    self.ways[0][0]
+  }
+
+  #[allow(dead_code)]
+  pub fn arrangements(&self) -> Arrangements<'_> {
+    let stack = if self.count() > 0 {vec![(0, 0, vec![])]} else {vec![]};
+    Arrangements { line: self, stack }
+  }
+
+  // Uniformly chosen arrangement; `random(n)` must return a uniform value
+  // below n.
+  #[allow(dead_code)]
+  pub fn sample<R: FnMut(u128) -> u128>(&self, mut random: R) -> Option<Vec<Cell>> {
+    if self.count() == 0 { return None; }
+    let (mut i, mut g) = (0, 0);
+    let mut result = vec![];
+    while i < self.cells.len() {
+      let skip = self.skip(i, g);
+      let take = self.take(i, g);
+      if random(self.ways[i][g]) < skip {
+        result.push(Cell::Operational);
+        i += 1;
+      } else {
+        let end = take.unwrap();
+        self.put(&mut result, i, g, end);
+        (i, g) = (end, g + 1);
+      }
+    }
+    Some(result)
+  }
+
+  // Every cell that has the same state in all arrangements gets that state,
+  // the others stay unknown; None if there is no arrangement.
+  pub fn deduce(&self) -> Option<Vec<Cell>> {
+    let total = self.count();
+    if total == 0 { return None; }
+    let (n, m) = (self.cells.len(), self.groups.len());
+    // Arrangements of the first i cells with the first g groups, forward.
+    let mut before = vec![vec![0_u128; m + 1]; n + 1];
+    before[0][0] = 1;
+    // Arrangements with a run starting at i, as differences whose prefix
+    // sums (exact despite the wrapping) count the runs covering a cell.
+    let mut damaged = vec![0_u128; n + 1];
+    for i in 0..n {
+      for g in 0..=m {
+        let v = before[i][g];
+        if v == 0 { continue; }
+        if self.cells[i] != Cell::Damaged { before[i + 1][g] += v; }
+        if let Some(end) = self.place(i, g) {
+          before[end][g + 1] += v;
+          let through = v * self.ways[end][g + 1];
+          damaged[i] = damaged[i].wrapping_add(through);
+          damaged[i + self.groups[g]] = damaged[i + self.groups[g]].wrapping_sub(through);
+        }
+      }
+    }
+    let mut acc = 0_u128;
+    Some(damaged[..n].iter().map(|&d| {
+      acc = acc.wrapping_add(d);
+      match acc {
+        0 => Cell::Operational,
+        a if a == total => Cell::Damaged,
+        _ => Cell::Unknown,
+      }
+    }).collect())
+  }
+}
+
+impl Iterator for Arrangements<'_> {
+  type Item = Vec<Cell>;
+
+  fn next(&mut self) -> Option<Vec<Cell>> {
+    let line = self.line;
+    while let Some((i, g, mut cells)) = self.stack.pop() {
+      if i == line.cells.len() { return Some(cells); }
+      // Pushed in reverse so that the operational branch comes out first.
+      if let Some(end) = line.take(i, g) {
+        let mut taken = cells.clone();
+        line.put(&mut taken, i, g, end);
+        self.stack.push((end, g + 1, taken));
+      }
+      if line.skip(i, g) > 0 {
+        cells.push(Cell::Operational);
+        self.stack.push((i + 1, g, cells));
+      }
+    }
+    None
+  }
+}
+
+#[allow(dead_code)]
+impl Nonogram {
+  // Propagates line deductions over rows and columns until nothing
+  // changes, then guesses on the first unknown cell. Returns at most
+  // `limit` solutions.
+  pub fn solve(&self, limit: usize) -> Vec<Grid> {
+    let unknown = vec![vec![Cell::Unknown; self.cols.len()]; self.rows.len()];
+    let mut result = vec![];
+    self.search(Grid { cells: unknown }, limit, &mut result);
+    result
+  }
+
+  fn search(&self, mut grid: Grid, limit: usize, result: &mut Vec<Grid>) {
+    if result.len() >= limit || !self.propagate(&mut grid) { return; }
+    let unknown = grid.cells.iter().enumerate().find_map(|(y, row)| {
+      row.iter().position(|&c| c == Cell::Unknown).map(|x| (x, y))
+    });
+    let Some((x, y)) = unknown else {
+      result.push(grid);
+      return;
+    };
+    for guess in [Cell::Damaged, Cell::Operational] {
+      let mut next = grid.clone();
+      next.cells[y][x] = guess;
+      self.search(next, limit, result);
+    }
+  }
+
+  // False on a contradiction.
+  fn propagate(&self, grid: &mut Grid) -> bool {
+    let mut dirty_rows = vec![true; self.rows.len()];
+    let mut dirty_cols = vec![true; self.cols.len()];
+    while dirty_rows.contains(&true) || dirty_cols.contains(&true) {
+      for (y, dirty) in dirty_rows.iter_mut().enumerate() {
+        if !std::mem::take(dirty) { continue; }
+        let Some(cells) = Line::new(&grid.cells[y], &self.rows[y]).deduce() else { return false };
+        for (x, c) in cells.into_iter().enumerate() {
+          if grid.cells[y][x] != c {
+            grid.cells[y][x] = c;
+            dirty_cols[x] = true;
+          }
+        }
+      }
+      for (x, dirty) in dirty_cols.iter_mut().enumerate() {
+        if !std::mem::take(dirty) { continue; }
+        let column = grid.cells.iter().map(|row| row[x]).collect::<Vec<_>>();
+        let Some(cells) = Line::new(&column, &self.cols[x]).deduce() else { return false };
+        for (y, c) in cells.into_iter().enumerate() {
+          if grid.cells[y][x] != c {
+            grid.cells[y][x] = c;
+            dirty_rows[y] = true;
+          }
+        }
+      }
+    }
+    true
+  }
+}
+
+impl fmt::Display for Grid {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for row in &self.cells {
+      let line = row.iter().map(|c| match c {
+        Cell::Operational => '.',
+        Cell::Damaged => '#',
+        Cell::Unknown => '?',
+      }).collect::<String>();
+      writeln!(f, "{}", line)?;
+    }
+    Ok(())
+  }
+}
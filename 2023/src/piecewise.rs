@@ -0,0 +1,130 @@
+// Total map on u64 that shifts each of a sequence of intervals by its own
+// offset. Piece i covers starts[i] up to the next start (the last one up
+// to u64::MAX); ranges are inclusive (first, last) pairs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Piecewise {
+  pieces: Vec<(u64, i128)>,
+}
+
+impl Piecewise {
+  pub fn identity() -> Piecewise {
+    Piecewise { pieces: vec![(0, 0)] }
+  }
+
+  // Map sending `len` values from `src` to `dest` for every (dest, src, len)
+  // and the rest to themselves; on overlaps the first entry wins.
+  pub fn from_ranges(ranges: &[(u64, u64, u64)]) -> Piecewise {
+    let mut result = Piecewise::identity();
+    for &(dest, src, len) in ranges.iter().rev() {
+      if len > 0 {
+        result.set((src, src + (len - 1)), dest as i128 - src as i128);
+      }
+    }
+    result
+  }
+
+  // Pieces as (first, last, offset).
+  pub fn pieces(&self) -> impl Iterator<Item = (u64, u64, i128)> + '_ {
+    self.pieces.iter().enumerate().map(|(i, &(start, offset))| {
+      let last = self.pieces.get(i + 1).map_or(u64::MAX, |p| p.0 - 1);
+      (start, last, offset)
+    })
+  }
+
+  // Overwrites the offset on a range.
+  fn set(&mut self, (first, last): (u64, u64), offset: i128) {
+    let mut pieces = vec![];
+    for (a, b, o) in self.pieces() {
+      if b < first || a > last {
+        pieces.push((a, o));
+        continue;
+      }
+      if a < first { pieces.push((a, o)); }
+      if pieces.last().is_none_or(|p| p.0 != first) { pieces.push((first, offset)); }
+      if b > last { pieces.push((last + 1, o)); }
+    }
+    self.pieces = pieces;
+    self.normalize();
+  }
+
+  // Merges neighbours with the same offset.
+  fn normalize(&mut self) {
+    self.pieces.dedup_by(|b, a| a.1 == b.1);
+  }
+
+  fn shift(value: u64, offset: i128) -> u64 {
+    u64::try_from(value as i128 + offset).expect("value mapped outside of u64")
+  }
+
+  fn find(&self, value: u64) -> usize {
+    self.pieces.partition_point(|p| p.0 <= value) - 1
+  }
+
+  #[allow(dead_code)]
+  pub fn apply(&self, value: u64) -> u64 {
+    Piecewise::shift(value, self.pieces[self.find(value)].1)
+  }
+
+  // Splits a range at piece boundaries: (first, last, offset) for each part.
+  fn split(&self, (first, last): (u64, u64)) -> impl Iterator<Item = (u64, u64, i128)> + '_ {
+    self.pieces().skip(self.find(first))
+      .take_while(move |&(a, _, _)| a <= last)
+      .map(move |(a, b, o)| (a.max(first), b.min(last), o))
+  }
+
+  // Image of a range, as one range per piece it touches.
+  #[allow(dead_code)]
+  pub fn image(&self, range: (u64, u64)) -> Vec<(u64, u64)> {
+    self.split(range).map(|(a, b, o)| (Piecewise::shift(a, o), Piecewise::shift(b, o))).collect()
+  }
+
+  // Values sent into a range, as one range per piece that reaches it.
+  #[allow(dead_code)]
+  pub fn preimage(&self, (first, last): (u64, u64)) -> Vec<(u64, u64)> {
+    let mut result = self.pieces().filter_map(|(a, b, o)| {
+      let (lo, hi) = (a as i128 + o, b as i128 + o);
+      let (lo, hi) = (lo.max(first as i128), hi.min(last as i128));
+      if lo > hi { return None; }
+      Some(((lo - o) as u64, (hi - o) as u64))
+    }).collect::<Vec<_>>();
+    result.sort();
+    result
+  }
+
+  // Smallest value in the image of a set of ranges.
+  pub fn min_image(&self, ranges: &[(u64, u64)]) -> Option<u64> {
+    ranges.iter().flat_map(|&r| self.split(r)).map(|(a, _, o)| Piecewise::shift(a, o)).min()
+  }
+
+  // `next` applied after `self`, as a single map.
+  pub fn then(&self, next: &Piecewise) -> Piecewise {
+    let mut pieces = vec![];
+    for (a, b, o) in self.pieces() {
+      let image = (Piecewise::shift(a, o), Piecewise::shift(b, o));
+      for (c, _, p) in next.split(image) {
+        pieces.push(((c as i128 - o) as u64, o + p));
+      }
+    }
+    let mut result = Piecewise { pieces };
+    result.normalize();
+    result
+  }
+
+  // The inverse map, None unless the pieces move onto a partition of u64.
+  #[allow(dead_code)]
+  pub fn inverse(&self) -> Option<Piecewise> {
+    let mut images = self.pieces()
+      .map(|(a, b, o)| (Piecewise::shift(a, o), Piecewise::shift(b, o), -o))
+      .collect::<Vec<_>>();
+    images.sort();
+    let mut next = Some(0);
+    for &(a, b, _) in &images {
+      if next != Some(a) { return None; }
+      next = b.checked_add(1);
+    }
+    if next.is_some() { return None; }
+    let mut result = Piecewise { pieces: images.into_iter().map(|(a, _, o)| (a, o)).collect() };
+    result.normalize();
+    Some(result)
+  }
+}
@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+pub const START: &str = "in";
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Target {
+  Workflow(String),
+  Accept,
+  Reject,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+  attribute: usize,
+  compare: Ordering,
+  value: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workflow {
+  rules: Vec<(Condition, Target)>,
+  default: Target,
+}
+
+// Hyper-rectangle over all attributes, one inclusive range per attribute.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+  pub range: Vec<RangeInclusive<u64>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Engine {
+  attributes: Vec<String>,
+  workflows: BTreeMap<String, Workflow>,
+}
+
+// Regions arriving at each target, and regions firing each rule (the
+// default rule of a workflow has index equal to the number of rules).
+#[derive(Default)]
+struct Flow {
+  arrivals: HashMap<Target, Vec<Region>>,
+  fired: HashMap<(String, usize), Vec<Region>>,
+}
+
+impl Target {
+  fn parse(text: &str) -> Target {
+    match text {
+      "A" => Target::Accept,
+      "R" => Target::Reject,
+      _ => Target::Workflow(String::from(text)),
+    }
+  }
+}
+
+impl fmt::Display for Target {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Target::Workflow(name) => write!(f, "{}", name),
+      Target::Accept => write!(f, "A"),
+      Target::Reject => write!(f, "R"),
+    }
+  }
+}
+
+impl Region {
+  pub fn new(size: usize, init: RangeInclusive<u64>) -> Region {
+    Region { range: vec![init; size] }
+  }
+
+  pub fn total(&self) -> u64 {
+    self.range.iter().map(|r| {
+      (r.end() + 1).saturating_sub(*r.start())
+    }).product::<u64>()
+  }
+
+  // Parts matching and not matching the condition.
+  fn split(&self, cond: &Condition) -> (Option<Region>, Option<Region>) {
+    let idx = cond.attribute;
+    let r = &self.range[idx];
+    let v = cond.value;
+    // Nothing is below zero or above the largest value.
+    let never = match cond.compare {
+      Ordering::Less => v == u64::MIN,
+      _ => v == u64::MAX,
+    };
+    if never { return (None, Some(self.clone())); }
+    let c1 = r.start().cmp(&v) == cond.compare;
+    let c2 = r.end().cmp(&v) == cond.compare;
+    match (c1, c2) {
+      (true, true) => (Some(self.clone()), None),
+      (false, false) => (None, Some(self.clone())),
+      _ => {
+        let (r1, r2) = match cond.compare {
+          Ordering::Less => (*r.start()..=v-1, v..=*r.end()),
+          Ordering::Greater => (v+1..=*r.end(), *r.start()..=v),
+          _ => panic!(),
+        };
+        let (mut x1, mut x2) = (self.clone(), self.clone());
+        x1.range[idx] = r1;
+        x2.range[idx] = r2;
+        (Some(x1), Some(x2))
+      },
+    }
+  }
+}
+
+impl Engine {
+  pub fn parse(text: &str) -> Engine {
+    let mut attributes = Vec::<String>::new();
+    let mut index = |name: &str| {
+      attributes.iter().position(|a| a == name).unwrap_or_else(|| {
+        attributes.push(String::from(name));
+        attributes.len() - 1
+      })
+    };
+    let mut workflows = BTreeMap::new();
+    for line in text.lines() {
+      let (name, body) = line.trim_end_matches('}').split_once('{').unwrap();
+      let a = body.split(',').collect::<Vec<_>>();
+      let n = a.len() - 1;
+      let rules = a[..n].iter().map(|s| {
+        let (s1, s2) = s.split_once(':').unwrap();
+        let pos = s1.find(['<', '>']).expect("unknown symbol");
+        let compare = if &s1[pos..=pos] == "<" {Ordering::Less} else {Ordering::Greater};
+        let value = s1[pos + 1..].parse::<u64>().unwrap();
+        (Condition { attribute: index(&s1[..pos]), compare, value }, Target::parse(s2))
+      }).collect::<Vec<_>>();
+      let default = Target::parse(a[n]);
+      workflows.insert(String::from(name), Workflow { rules, default });
+    }
+    Engine { attributes, workflows }
+  }
+
+  pub fn domain(&self, init: RangeInclusive<u64>) -> Region {
+    Region::new(self.attributes.len(), init)
+  }
+
+  // Runs a single part (missing attributes count as zero).
+  pub fn process(&self, part: &HashMap<String, u64>) -> Target {
+    let value = |idx: usize| *part.get(&self.attributes[idx]).unwrap_or(&0);
+    let mut key = String::from(START);
+    while let Some(wf) = self.workflows.get(&key) {
+      let target = wf.rules.iter()
+        .find(|(c, _)| value(c.attribute).cmp(&c.value) == c.compare)
+        .map_or(&wf.default, |(_, t)| t);
+      match target {
+        Target::Workflow(to) => key = to.clone(),
+        _ => return target.clone(),
+      }
+    }
+    panic!("{}", key);
+  }
+
+  fn descend(&self, key: &str, mut region: Region, path: &mut Vec<String>, flow: &mut Flow) {
+    assert!(!path.iter().any(|p| p == key), "workflow cycle at {}", key);
+    let wf = self.workflows.get(key).unwrap_or_else(|| panic!("{}", key));
+    flow.arrivals.entry(Target::Workflow(String::from(key))).or_default().push(region.clone());
+    path.push(String::from(key));
+    let rules = wf.rules.iter().map(|(c, t)| (Some(c), t))
+      .chain([(None, &wf.default)]);
+    for (i, (cond, target)) in rules.enumerate() {
+      let (hit, rest) = match cond {
+        Some(c) => region.split(c),
+        None => (Some(region.clone()), None),
+      };
+      if let Some(r) = hit {
+        flow.fired.entry((String::from(key), i)).or_default().push(r.clone());
+        match target {
+          Target::Workflow(to) => self.descend(to, r, path, flow),
+          _ => flow.arrivals.entry(target.clone()).or_default().push(r),
+        }
+      }
+      match rest {
+        Some(r) => region = r,
+        None => break,
+      }
+    }
+    path.pop();
+  }
+
+  fn flow(&self, domain: &Region) -> Flow {
+    let mut flow = Flow::default();
+    self.descend(START, domain.clone(), &mut vec![], &mut flow);
+    flow
+  }
+
+  // Disjoint regions of the domain that reach the given workflow or outcome.
+  pub fn reaching(&self, target: &Target, domain: &Region) -> Vec<Region> {
+    self.flow(domain).arrivals.remove(target).unwrap_or_default()
+  }
+
+  pub fn count(&self, target: &Target, domain: &Region) -> u64 {
+    self.reaching(target, domain).iter().map(|r| r.total()).sum()
+  }
+
+  // Rules (workflow, index) that no part of the domain can ever fire,
+  // including every rule of unreachable workflows.
+  #[allow(dead_code)]
+  pub fn dead_rules(&self, domain: &Region) -> Vec<(String, usize)> {
+    let flow = self.flow(domain);
+    self.workflows.iter().flat_map(|(name, wf)| {
+      (0..wf.rules.len()).map(move |i| (name.clone(), i))
+    }).filter(|k| !flow.fired.contains_key(k)).collect()
+  }
+
+  // Equivalent engine (for parts inside the domain) without dead rules,
+  // redundant rules or workflows that always lead to the same target.
+  #[allow(dead_code)]
+  pub fn simplify(&self, domain: &Region) -> Engine {
+    let mut engine = self.clone();
+    loop {
+      let flow = engine.flow(domain);
+      let mut next = engine.clone();
+      next.workflows.retain(|name, _| {
+        flow.arrivals.contains_key(&Target::Workflow(name.clone()))
+      });
+      for (name, wf) in next.workflows.iter_mut() {
+        let fired = |i: usize| flow.fired.contains_key(&(name.clone(), i));
+        if !fired(wf.rules.len()) {
+          if let Some((_, target)) = wf.rules.pop() {
+            wf.default = target;
+          }
+        }
+        let mut i = 0;
+        wf.rules.retain(|_| { i += 1; fired(i - 1) });
+        while wf.rules.last().is_some_and(|(_, t)| *t == wf.default) {
+          wf.rules.pop();
+        }
+      }
+      let mut aliases = next.workflows.iter()
+        .filter(|(name, wf)| wf.rules.is_empty() && *name != START)
+        .map(|(name, wf)| (Target::Workflow(name.clone()), wf.default.clone()))
+        .collect::<HashMap<_,_>>();
+      for key in aliases.keys().cloned().collect::<Vec<_>>() {
+        let mut to = aliases[&key].clone();
+        while let Some(t) = aliases.get(&to) { to = t.clone(); }
+        aliases.insert(key, to);
+      }
+      next.workflows.retain(|name, _| !aliases.contains_key(&Target::Workflow(name.clone())));
+      for wf in next.workflows.values_mut() {
+        let targets = wf.rules.iter_mut().map(|(_, t)| t).chain([&mut wf.default]);
+        for t in targets {
+          if let Some(to) = aliases.get(t) { *t = to.clone(); }
+        }
+      }
+      if next.to_string() == engine.to_string() { return next; }
+      engine = next;
+    }
+  }
+}
+
+impl fmt::Display for Engine {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let names = self.workflows.keys().filter(|k| *k == START)
+      .chain(self.workflows.keys().filter(|k| *k != START));
+    for name in names {
+      let wf = &self.workflows[name];
+      write!(f, "{}{{", name)?;
+      for (c, t) in &wf.rules {
+        let op = if c.compare == Ordering::Less {'<'} else {'>'};
+        write!(f, "{}{}{}:{},", self.attributes[c.attribute], op, c.value, t)?;
+      }
+      writeln!(f, "{}}}", wf.default)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,24 @@
+// Small deterministic generator for the randomized tests.
+pub struct XorShift {
+  state: u64,
+}
+
+impl XorShift {
+  // The seed must not be zero.
+  pub fn new(seed: u64) -> XorShift {
+    assert_ne!(seed, 0);
+    XorShift { state: seed }
+  }
+
+  pub fn next(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  // Value below n; the modulo bias is fine for tests.
+  pub fn below(&mut self, n: u64) -> u64 {
+    self.next() % n
+  }
+}
@@ -13,6 +13,8 @@ mod day09;
 mod day10;
 mod day11;
 mod day12;
+#[path = "../../common/polygon.rs"]
+mod polygon;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
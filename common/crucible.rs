@@ -0,0 +1,243 @@
+// Shortest paths on a grid of entry costs where movement is constrained:
+// how far the walker must and may go in a straight line, which turns it
+// may take and what a turn costs.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+pub type Point = (usize, usize);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Turn {
+    Straight,
+    Right,
+    Back,
+    Left,
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+#[derive(Clone, Debug)]
+pub struct Rules {
+    // Steps in a line before the walker may turn or stop.
+    pub min_run: usize,
+    // Steps in a line before it must turn; usize::MAX for no limit.
+    pub max_run: usize,
+    // Added to the entry cost of the first cell after a turn.
+    pub turn_cost: u64,
+    // Turns allowed after the first step; Straight is always allowed.
+    pub turns: Vec<Turn>,
+    // Guide the search with the distance to the end times the cheapest
+    // cell (A*); the result is the same.
+    pub heuristic: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    pub pos: Point,
+    pub dir: Direction,
+    pub cost: u64,
+}
+
+// Steps after the start, each with what entering the cell cost.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    pub start: Point,
+    pub steps: Vec<Step>,
+    pub total: u64,
+}
+
+impl Direction {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn turn(self, other: Direction) -> Turn {
+        match (other.index() + 4 - self.index()) % 4 {
+            0 => Turn::Straight,
+            1 => Turn::Right,
+            2 => Turn::Back,
+            _ => Turn::Left,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        }
+    }
+
+    fn step(self, (x, y): Point, width: usize, height: usize) -> Option<Point> {
+        match self {
+            Direction::Up if y > 0 => Some((x, y - 1)),
+            Direction::Right if x + 1 < width => Some((x + 1, y)),
+            Direction::Down if y + 1 < height => Some((x, y + 1)),
+            Direction::Left if x > 0 => Some((x - 1, y)),
+            _ => None,
+        }
+    }
+}
+
+impl Rules {
+    // Free movement: any turn, any run length.
+    #[allow(dead_code)]
+    pub fn free() -> Rules {
+        Rules {
+            min_run: 1,
+            max_run: usize::MAX,
+            turn_cost: 0,
+            turns: vec![Turn::Left, Turn::Right, Turn::Back],
+            heuristic: false,
+        }
+    }
+
+    // Left and right turns only, with straight runs between the limits.
+    pub fn crucible(min_run: usize, max_run: usize) -> Rules {
+        Rules {
+            min_run: min_run.max(1),
+            max_run,
+            turn_cost: 0,
+            turns: vec![Turn::Left, Turn::Right],
+            heuristic: false,
+        }
+    }
+}
+
+// Cheapest path from `start` to `end` entering cells at `cost(p)`; None
+// marks a cell that cannot be entered. The start cell costs nothing.
+pub fn shortest_path<F>(width: usize, height: usize, cost: F, start: Point, end: Point,
+                        rules: &Rules) -> Option<Path>
+    where F: Fn(Point) -> Option<u64>
+{
+    // Runs past `cap` behave the same, so they share a state.
+    let cap = if rules.max_run == usize::MAX {rules.min_run} else {rules.max_run};
+    // State: cell, direction (4 before the first step) and run length.
+    let encode = |p: Point, dir: usize, run: usize| ((p.1 * width + p.0) * 5 + dir) * (cap + 1) + run;
+    let decode = |s: usize| {
+        let (cell, rest) = (s / (5 * (cap + 1)), s % (5 * (cap + 1)));
+        ((cell % width, cell / width), rest / (cap + 1), rest % (cap + 1))
+    };
+    let cheapest = if rules.heuristic {
+        (0..width * height).filter_map(|i| cost((i % width, i / width))).min().unwrap_or(0)
+    } else {
+        0
+    };
+    let estimate = |(x, y): Point| {
+        let distance = (x as i64 - end.0 as i64).abs() + (y as i64 - end.1 as i64).abs();
+        distance as u64 * cheapest
+    };
+
+    let size = width * height * 5 * (cap + 1);
+    let mut best = vec![u64::MAX; size];
+    let mut prev = vec![usize::MAX; size];
+    let mut heap = BinaryHeap::new();
+    let first = encode(start, 4, 0);
+    best[first] = 0;
+    heap.push(Reverse((estimate(start), 0, first)));
+    while let Some(Reverse((_, dist, state))) = heap.pop() {
+        if dist > best[state] { continue; }
+        let (pos, dir, run) = decode(state);
+        if pos == end && (run >= rules.min_run || pos == start) {
+            return Some(trace(&prev, &best, state, decode, start));
+        }
+        for &next in &DIRECTIONS {
+            let (turn, new_run) = if dir == 4 {
+                (Turn::Straight, 1)
+            } else {
+                let turn = DIRECTIONS[dir].turn(next);
+                if turn == Turn::Straight {
+                    if run >= rules.max_run { continue; }
+                    (turn, (run + 1).min(cap))
+                } else {
+                    if run < rules.min_run || !rules.turns.contains(&turn) { continue; }
+                    (turn, 1)
+                }
+            };
+            let p = match next.step(pos, width, height) {
+                Some(p) => p,
+                None => continue,
+            };
+            let c = match cost(p) {
+                Some(c) => c,
+                None => continue,
+            };
+            let extra = if turn == Turn::Straight {0} else {rules.turn_cost};
+            let s = encode(p, next.index(), new_run);
+            let d = dist + c + extra;
+            if d < best[s] {
+                best[s] = d;
+                prev[s] = state;
+                heap.push(Reverse((d + estimate(p), d, s)));
+            }
+        }
+    }
+    None
+}
+
+fn trace<D>(prev: &[usize], best: &[u64], last: usize, decode: D, start: Point) -> Path
+    where D: Fn(usize) -> (Point, usize, usize)
+{
+    let mut steps = vec![];
+    let mut state = last;
+    while prev[state] != usize::MAX {
+        let (pos, dir, _) = decode(state);
+        let cost = best[state] - best[prev[state]];
+        steps.push(Step { pos, dir: DIRECTIONS[dir], cost });
+        state = prev[state];
+    }
+    steps.reverse();
+    Path { start, steps, total: best[last] }
+}
+
+impl Path {
+    // The grid drawn by `cell`, with an arrow on every cell of the path.
+    #[allow(dead_code)]
+    pub fn render<C: Fn(Point) -> char>(&self, width: usize, height: usize, cell: C) -> String {
+        let mut rows: Vec<Vec<char>> = (0..height).map(|y| (0..width).map(|x| cell((x, y))).collect()).collect();
+        for step in &self.steps {
+            rows[step.pos.1][step.pos.0] = step.dir.arrow();
+        }
+        rows.into_iter().map(|r| r.into_iter().collect::<String>() + "\n").collect()
+    }
+
+    // Cells shaded from light (cheap) to dark (expensive), walls in red,
+    // and the path as a line through the cell centres.
+    #[allow(dead_code)]
+    pub fn svg<F: Fn(Point) -> Option<u64>>(&self, width: usize, height: usize, cost: F) -> String {
+        const CELL: usize = 10;
+        let max = (0..width * height).filter_map(|i| cost((i % width, i / width))).max().unwrap_or(0).max(1);
+        let mut out = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+                              width * CELL, height * CELL);
+        for y in 0..height {
+            for x in 0..width {
+                let fill = match cost((x, y)) {
+                    Some(c) => {
+                        let v = 255 - (c * 200 / max) as usize;
+                        format!("rgb({},{},{})", v, v, v)
+                    },
+                    None => String::from("red"),
+                };
+                out += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                                x * CELL, y * CELL, CELL, CELL, fill);
+            }
+        }
+        let centre = |(x, y): Point| format!("{},{}", x * CELL + CELL / 2, y * CELL + CELL / 2);
+        let points: Vec<String> = Some(self.start).into_iter()
+            .chain(self.steps.iter().map(|s| s.pos)).map(centre).collect();
+        out += &format!("<polyline points=\"{}\" fill=\"none\" stroke=\"blue\" stroke-width=\"2\"/>\n",
+                        points.join(" "));
+        out + "</svg>\n"
+    }
+}
@@ -0,0 +1,109 @@
+// Block letters drawn by the "read the screen" puzzles: the 4x6 font (one
+// blank column between letters) and the 6x10 font (two blank columns).
+// Glyphs are matched column by column, so the spacing does not matter.
+
+const SMALL: &[(char, &[&str])] = &[
+    ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', &["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+const LARGE: &[(char, &[&str])] = &[
+    ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#",
+            "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', &[".####.", "#....#", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', &["######", "#.....", "#.....", "#.....", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', &["######", "#.....", "#.....", "#.....", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', &[".####.", "#....#", "#.....", "#.....", "#.....",
+            "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', &["#....#", "#....#", "#....#", "#....#", "######",
+            "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', &["...###", "....#.", "....#.", "....#.", "....#.",
+            "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....",
+            "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', &["#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#",
+            "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..",
+            "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', &["######", ".....#", ".....#", "....#.", "...#..",
+            "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+// One bitmask per column, bit i set for a lit pixel in row i.
+fn columns(rows: &[&str]) -> Vec<u16> {
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    (0..width).map(|x| {
+        rows.iter().enumerate()
+            .filter(|&(_, r)| r.as_bytes().get(x) == Some(&b'#'))
+            .fold(0, |acc, (y, _)| acc | 1 << y)
+    }).collect()
+}
+
+// Lit pixels to letters; the glyph height is that of the bounding box.
+pub fn read_points<I: IntoIterator<Item = (i64, i64)>>(points: I) -> Option<String> {
+    let points: Vec<(i64, i64)> = points.into_iter().collect();
+    let x0 = points.iter().map(|p| p.0).min()?;
+    let x1 = points.iter().map(|p| p.0).max()?;
+    let y0 = points.iter().map(|p| p.1).min()?;
+    let y1 = points.iter().map(|p| p.1).max()?;
+    let font = match y1 - y0 + 1 {
+        6 => SMALL,
+        10 => LARGE,
+        _ => return None,
+    };
+    let mut screen = vec![0_u16; (x1 - x0 + 1) as usize];
+    for &(x, y) in &points {
+        screen[(x - x0) as usize] |= 1 << (y - y0);
+    }
+    let glyphs: Vec<(char, Vec<u16>)> = font.iter().map(|&(c, rows)| (c, columns(rows))).collect();
+
+    let mut result = String::new();
+    let mut x = 0;
+    while x < screen.len() {
+        if screen[x] == 0 {
+            x += 1;
+            continue;
+        }
+        let &(c, ref cols) = glyphs.iter()
+            .filter(|g| screen[x..].starts_with(&g.1))
+            .max_by_key(|g| g.1.len())?;
+        result.push(c);
+        x += cols.len();
+    }
+    Some(result)
+}
+
+// A rendered screen to letters; `on` marks the lit pixels.
+#[allow(dead_code)]
+pub fn read_bitmap(text: &str, on: char) -> Option<String> {
+    read_points(text.lines().enumerate().flat_map(|(y, line)| {
+        line.match_indices(on).map(move |(x, _)| (x as i64, y as i64))
+    }))
+}
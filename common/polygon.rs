@@ -0,0 +1,143 @@
+// Closed polygons on the integer lattice, mostly rectilinear ones: a loop
+// of tiles, or a trench dug along grid lines. Areas are exact in i128.
+
+pub type Point = (i64, i64);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+// Vertices in order; the last one connects back to the first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub vertices: Vec<Point>,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {a.abs()} else {gcd(b, a % b)}
+}
+
+fn cross(o: Point, a: Point, b: Point) -> i128 {
+    let (ax, ay) = ((a.0 - o.0) as i128, (a.1 - o.1) as i128);
+    let (bx, by) = ((b.0 - o.0) as i128, (b.1 - o.1) as i128);
+    ax * by - ay * bx
+}
+
+fn within(p: Point, a: Point, b: Point) -> bool {
+    p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0) && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1)
+}
+
+fn on_segment(p: Point, a: Point, b: Point) -> bool {
+    cross(a, b, p) == 0 && within(p, a, b)
+}
+
+// Closed segments ab and cd share at least one point.
+fn segments_meet(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if d1.signum() * d2.signum() < 0 && d3.signum() * d4.signum() < 0 { return true; }
+    on_segment(a, c, d) || on_segment(b, c, d) || on_segment(c, a, b) || on_segment(d, a, b)
+}
+
+impl Polygon {
+    // Drops repeated vertices and a closing copy of the first one.
+    pub fn new(mut vertices: Vec<Point>) -> Polygon {
+        vertices.dedup();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        Polygon { vertices }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    // Twice the signed area (shoelace): positive when the vertices go
+    // counterclockwise with y pointing up.
+    pub fn double_area(&self) -> i128 {
+        self.edges().map(|(a, b)| a.0 as i128 * b.1 as i128 - b.0 as i128 * a.1 as i128).sum()
+    }
+
+    #[allow(dead_code)]
+    pub fn area(&self) -> i128 {
+        self.double_area().abs() / 2
+    }
+
+    // Length of the outline; for diagonal edges the Manhattan length.
+    #[allow(dead_code)]
+    pub fn perimeter(&self) -> i128 {
+        self.edges().map(|(a, b)| ((b.0 - a.0).abs() + (b.1 - a.1).abs()) as i128).sum()
+    }
+
+    // Lattice points on the outline.
+    pub fn boundary_points(&self) -> i128 {
+        self.edges().map(|(a, b)| gcd((b.0 - a.0) as i128, (b.1 - a.1) as i128)).sum()
+    }
+
+    // Lattice points strictly inside, by Pick's theorem A = I + B/2 - 1.
+    pub fn interior_points(&self) -> i128 {
+        (self.double_area().abs() - self.boundary_points() + 2) / 2
+    }
+
+    // Tiles covered by the outline and everything it encloses.
+    pub fn covered_points(&self) -> i128 {
+        self.interior_points() + self.boundary_points()
+    }
+
+    // Where a point is, in coordinates scaled by two so that centres of
+    // cells between lattice points can be asked about too.
+    pub fn locate_doubled(&self, (px, py): Point) -> Location {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let (a, b) = ((a.0 * 2, a.1 * 2), (b.0 * 2, b.1 * 2));
+            if on_segment((px, py), a, b) { return Location::Boundary; }
+            // Crossings of the ray going right from the point.
+            if (a.1 > py) != (b.1 > py) {
+                let side = cross(a, b, (px, py));
+                if (side > 0) == (b.1 > a.1) { inside = !inside; }
+            }
+        }
+        if inside {Location::Inside} else {Location::Outside}
+    }
+
+    pub fn locate(&self, (x, y): Point) -> Location {
+        self.locate_doubled((x * 2, y * 2))
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.locate(p) != Location::Outside
+    }
+
+    // Two edges that meet although they are not neighbours (or that fold
+    // back over each other), None for a simple polygon.
+    pub fn self_intersection(&self) -> Option<(usize, usize)> {
+        let edges: Vec<(Point, Point)> = self.edges().collect();
+        let n = edges.len();
+        for i in 0..n {
+            for j in i + 1..n {
+                let (a, b) = edges[i];
+                let (c, d) = edges[j];
+                let adjacent = j == i + 1 || (i == 0 && j == n - 1);
+                if !adjacent {
+                    if segments_meet(a, b, c, d) { return Some((i, j)); }
+                } else if n > 2 {
+                    // Neighbours share one vertex; more than that is an overlap.
+                    let (shared, p, q) = if j == i + 1 {(b, a, d)} else {(a, b, c)};
+                    if cross(shared, p, q) == 0 && (on_segment(p, shared, q) || on_segment(q, shared, p)) {
+                        return Some((i, j));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_simple(&self) -> bool {
+        self.vertices.len() >= 3 && self.self_intersection().is_none()
+    }
+}
@@ -0,0 +1,149 @@
+// Exact fractions for the puzzles that must not round: solving a monkey
+// equation, or crossing hailstone paths. The checked_* methods report
+// overflow and division by zero; the operators panic on them instead.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Kept in lowest terms with a positive denominator.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {a.abs()} else {gcd(b, a % b)}
+}
+
+fn overflow<T>(value: Option<T>) -> Result<T, String> {
+    value.ok_or_else(|| String::from("arithmetic overflow"))
+}
+
+fn expect(value: Result<Rational, String>) -> Rational {
+    value.unwrap_or_else(|e| panic!("{}", e))
+}
+
+impl Rational {
+    pub fn checked_new(num: i128, den: i128) -> Result<Rational, String> {
+        if den == 0 { return Err(String::from("division by zero")); }
+        let g = gcd(num, den).max(1) * den.signum();
+        Ok(Rational { num: overflow(num.checked_div(g))?, den: overflow(den.checked_div(g))? })
+    }
+
+    #[allow(dead_code)]
+    pub fn new(num: i128, den: i128) -> Rational {
+        expect(Rational::checked_new(num, den))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    #[allow(dead_code)]
+    pub fn to_integer(self) -> Option<i128> {
+        if self.is_integer() {Some(self.num)} else {None}
+    }
+
+    pub fn signum(&self) -> i128 {
+        self.num.signum()
+    }
+
+    pub fn checked_add(self, other: Rational) -> Result<Rational, String> {
+        let g = gcd(self.den, other.den);
+        let a = overflow(self.num.checked_mul(other.den / g))?;
+        let b = overflow(other.num.checked_mul(self.den / g))?;
+        Rational::checked_new(overflow(a.checked_add(b))?, overflow((self.den / g).checked_mul(other.den))?)
+    }
+
+    pub fn checked_sub(self, other: Rational) -> Result<Rational, String> {
+        let g = gcd(self.den, other.den);
+        let a = overflow(self.num.checked_mul(other.den / g))?;
+        let b = overflow(other.num.checked_mul(self.den / g))?;
+        Rational::checked_new(overflow(a.checked_sub(b))?, overflow((self.den / g).checked_mul(other.den))?)
+    }
+
+    pub fn checked_mul(self, other: Rational) -> Result<Rational, String> {
+        // Cancel crosswise first to keep the products small.
+        let (g1, g2) = (gcd(self.num, other.den).max(1), gcd(other.num, self.den).max(1));
+        let num = overflow((self.num / g1).checked_mul(other.num / g2))?;
+        let den = overflow((self.den / g2).checked_mul(other.den / g1))?;
+        Rational::checked_new(num, den)
+    }
+
+    pub fn checked_div(self, other: Rational) -> Result<Rational, String> {
+        if other.is_zero() { return Err(String::from("division by zero")); }
+        self.checked_mul(Rational::checked_new(other.den, other.num)?)
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(value: i128) -> Rational {
+        Rational { num: value, den: 1 }
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        expect(overflow(self.num.checked_neg()).map(|num| Rational { num, den: self.den }))
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        expect(self.checked_add(other))
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Rational) -> Rational {
+        expect(self.checked_sub(other))
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        expect(self.checked_mul(other))
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, other: Rational) -> Rational {
+        expect(self.checked_div(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        let a = overflow(self.num.checked_mul(other.den));
+        let b = overflow(other.num.checked_mul(self.den));
+        a.and_then(|a| b.map(|b| a.cmp(&b))).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {write!(f, "{}", self.num)} else {write!(f, "{}/{}", self.num, self.den)}
+    }
+}